@@ -0,0 +1,73 @@
+//! Reporting of shard locks that are held for too long, enabled by the `guard-tracing` feature.
+
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+struct Config {
+    hook: Option<fn(Duration, &'static str)>,
+    threshold: Duration,
+}
+
+static CONFIG: RwLock<Config> = RwLock::new(Config {
+    hook: None,
+    threshold: Duration::from_millis(100),
+});
+
+/// Sets the function called when a reference into a map or set is dropped after keeping its shard
+/// locked for longer than the threshold set by [`set_guard_hold_threshold`].
+///
+/// The hook receives how long the shard was locked, and whether it was locked for `"read"` or `"write"`.
+/// It is called after the shard has been unlocked. No hook is set by default.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// dashmap::set_guard_hold_hook(|held, kind| eprintln!("{kind} lock held for {held:?}"));
+/// dashmap::set_guard_hold_threshold(Duration::from_millis(10));
+/// ```
+pub fn set_guard_hold_hook(hook: fn(Duration, &'static str)) {
+    CONFIG.write().unwrap_or_else(|e| e.into_inner()).hook = Some(hook);
+}
+
+/// Sets how long a shard may be locked through a single reference before the hook set by
+/// [`set_guard_hold_hook`] is called. Defaults to 100 milliseconds.
+pub fn set_guard_hold_threshold(threshold: Duration) {
+    CONFIG.write().unwrap_or_else(|e| e.into_inner()).threshold = threshold;
+}
+
+pub(crate) fn report(acquired: Instant, kind: &'static str) {
+    let held = acquired.elapsed();
+    let config = CONFIG.read().unwrap_or_else(|e| e.into_inner());
+    if let Some(hook) = config.hook {
+        if held >= config.threshold {
+            drop(config);
+            hook(held, kind);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+    #[test]
+    fn hook_fires() {
+        super::set_guard_hold_threshold(Duration::ZERO);
+        super::set_guard_hold_hook(|_, kind| {
+            if kind == "write" {
+                WRITES.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        let map = DashMap::new();
+        let before = WRITES.load(Ordering::Relaxed);
+        drop(map.entry(1).or_insert(1));
+        assert!(WRITES.load(Ordering::Relaxed) > before);
+    }
+}
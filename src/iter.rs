@@ -23,13 +23,18 @@ use std::sync::Arc;
 pub struct OwningIter<K, V> {
     shards: std::vec::IntoIter<CachePadded<RwLock<HashMap<K, V>>>>,
     current: Option<GuardOwningIter<K, V>>,
+    remaining: usize,
 }
 
 impl<K: Eq + Hash, V> OwningIter<K, V> {
     pub(crate) fn new<S>(map: DashMap<K, V, S>) -> Self {
+        let mut shards = map.shards.into_vec();
+        let remaining = shards.iter_mut().map(|shard| shard.get_mut().len()).sum();
+
         Self {
-            shards: map.shards.into_vec().into_iter(),
+            shards: shards.into_iter(),
             current: None,
+            remaining,
         }
     }
 }
@@ -43,6 +48,7 @@ impl<K: Eq + Hash, V> Iterator for OwningIter<K, V> {
         loop {
             if let Some(current) = self.current.as_mut() {
                 if let Some((k, v)) = current.next() {
+                    self.remaining -= 1;
                     return Some((k, v));
                 }
             }
@@ -51,6 +57,79 @@ impl<K: Eq + Hash, V> Iterator for OwningIter<K, V> {
             self.current = Some(iter);
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K: Eq + Hash, V> ExactSizeIterator for OwningIter<K, V> {}
+
+/// Iterator removing the entries of a DashMap and yielding them as owned key value pairs,
+/// see [`DashMap::drain`].
+///
+/// Each shard is emptied in one go when the iterator reaches it, and is unlocked before any of its
+/// entries are yielded. Entries inserted into a shard after it has been emptied stay in the map.
+/// If the iterator is dropped early, the shards it has not reached yet are emptied as well,
+/// unless it was created by [`DashMap::drain_keep_rest`].
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::DashMap;
+///
+/// let map = DashMap::new();
+/// map.insert("hello", "world");
+/// let pairs: Vec<_> = map.drain().collect();
+/// assert_eq!(pairs, [("hello", "world")]);
+/// assert!(map.is_empty());
+/// ```
+pub struct Drain<'a, K, V> {
+    shards: std::slice::Iter<'a, CachePadded<RwLock<HashMap<K, V>>>>,
+    current: Option<GuardOwningIter<K, V>>,
+    /// Whether the shards not reached yet are emptied when the iterator is dropped.
+    clear_rest: bool,
+}
+
+impl<'a, K: Eq + Hash + 'a, V: 'a> Drain<'a, K, V> {
+    pub(crate) fn new<S>(map: &'a DashMap<K, V, S>, clear_rest: bool) -> Self {
+        Self {
+            shards: map.shards.iter(),
+            current: None,
+            clear_rest,
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash + 'a, V: 'a> Iterator for Drain<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                if let Some((k, v)) = current.next() {
+                    return Some((k, v));
+                }
+            }
+
+            let shard = core::mem::take(&mut *self.shards.next()?.write());
+            self.current = Some(shard.into_iter());
+        }
+    }
+}
+
+impl<K, V> Drop for Drain<'_, K, V> {
+    fn drop(&mut self) {
+        if !self.clear_rest {
+            return;
+        }
+
+        for shard in self.shards.by_ref() {
+            let table = core::mem::take(&mut *shard.write());
+            // The entries are dropped after the shard has been unlocked.
+            drop(table);
+        }
+    }
 }
 
 type GuardIter<'a, K, V> = (
@@ -159,7 +238,15 @@ impl<'a, K: Eq + Hash + 'a, V: 'a> Iterator for IterMut<'a, K, V> {
                 }
             }
 
-            let guard = self.shards.next()?.write();
+            let shard = self.shards.next()?;
+
+            let guard = shard.write();
+
+            // Empty shards are common in sparse maps. Skipping them here releases the lock
+            // right away and saves setting up a guard that no entry would share.
+            if guard.is_empty() {
+                continue;
+            }
 
             // SAFETY: we keep the guard alive with the shard iterator,
             // and with any refs produced by the iterator
@@ -204,6 +291,116 @@ mod tests {
         assert_eq!(map.iter_mut().count(), 1);
     }
 
+    #[test]
+    fn iter_mut_sparse() {
+        let map = DashMap::with_shard_amount(64);
+
+        map.insert(1, 1);
+        map.insert(2, 2);
+
+        map.iter_mut().for_each(|mut r| *r *= 10);
+
+        let mut values: Vec<_> = map.iter().map(|r| *r).collect();
+        values.sort_unstable();
+        assert_eq!(values, [10, 20]);
+
+        let empty: DashMap<i32, i32> = DashMap::with_shard_amount(64);
+        assert_eq!(empty.iter_mut().count(), 0);
+    }
+
+    #[test]
+    fn drain_all_shards() {
+        let map = DashMap::with_shard_amount(16);
+        for i in 0..1000 {
+            map.insert(i, i * 3);
+        }
+
+        let mut pairs: Vec<_> = map.drain().collect();
+        pairs.sort_unstable();
+        assert!(pairs.into_iter().eq((0..1000).map(|i| (i, i * 3))));
+        assert!(map.is_empty());
+        assert!(map.shard_lengths().iter().all(|&len| len == 0));
+    }
+
+    #[test]
+    fn drain_dropped_early() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+
+        let mut drain = map.drain();
+        let (first, _) = drain.next().unwrap();
+        assert!(!map.contains_key(&first));
+
+        // The shard being drained is already unlocked and can be refilled.
+        map.insert(first, 0);
+        drop(drain);
+
+        assert_eq!(map.len(), 1);
+        assert_eq!(*map.get(&first).unwrap(), 0);
+    }
+
+    #[test]
+    fn drain_keep_rest_dropped_early() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+
+        let shard_of = |key: &i32| map.determine_shard(map.hash_u64(key) as usize);
+        let mut drain = map.drain_keep_rest();
+        let (first, _) = drain.next().unwrap();
+        drop(drain);
+
+        // Only the shard that was reached has been emptied.
+        assert!(map.iter().all(|r| shard_of(r.key()) != shard_of(&first)));
+        assert_eq!(
+            map.len(),
+            (0..100).filter(|k| shard_of(k) != shard_of(&first)).count()
+        );
+
+        let left = map.len();
+        assert_eq!(map.drain_keep_rest().count(), left);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn drain_dropped_early_unlocks_before_dropping_values() {
+        struct Probe(&'static DashMap<i32, Probe>);
+
+        impl Drop for Probe {
+            fn drop(&mut self) {
+                // Deadlocks if the shard of the value is still locked.
+                let _ = self.0.len();
+            }
+        }
+
+        let map: &'static DashMap<i32, Probe> = Box::leak(Box::new(DashMap::with_shard_amount(4)));
+        for i in 0..100 {
+            map.insert(i, Probe(map));
+        }
+
+        let mut drain = map.drain();
+        drain.next();
+        drop(drain);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn into_iter_size_hint() {
+        let map = DashMap::with_shard_amount(8);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+
+        let mut iter = map.into_iter();
+        assert_eq!(iter.len(), 100);
+        iter.by_ref().take(40).for_each(drop);
+        assert_eq!(iter.size_hint(), (60, Some(60)));
+        assert_eq!(iter.count(), 60);
+    }
+
     #[test]
     fn iter_count() {
         let map = DashMap::new();
@@ -17,6 +17,30 @@ impl<K: Eq + Hash> Iterator for OwningIter<K> {
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next().map(|(k, _)| k)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: Eq + Hash> ExactSizeIterator for OwningIter<K> {}
+
+pub struct Drain<'a, K> {
+    inner: crate::iter::Drain<'a, K, ()>,
+}
+
+impl<'a, K: Eq + Hash + 'a> Drain<'a, K> {
+    pub(crate) fn new(inner: crate::iter::Drain<'a, K, ()>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, K: Eq + Hash + 'a> Iterator for Drain<'a, K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
 }
 
 pub struct Iter<'a, K> {
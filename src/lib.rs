@@ -3,9 +3,14 @@
 
 #[cfg(feature = "arbitrary")]
 mod arbitrary;
+#[cfg(feature = "guard-tracing")]
+mod guard_tracing;
 pub mod iter;
 pub mod iter_set;
+mod loading;
 mod lock;
+mod map_diff;
+mod map_view;
 pub mod mapref;
 mod read_only;
 #[cfg(feature = "serde")]
@@ -35,19 +40,26 @@ use cfg_if::cfg_if;
 use core::fmt;
 use core::hash::{BuildHasher, Hash, Hasher};
 use core::iter::FromIterator;
-use core::ops::{BitAnd, BitOr, Shl, Shr, Sub};
+use core::ops::{BitAnd, BitOr, RangeBounds, Shl, Shr, Sub};
 use crossbeam_utils::CachePadded;
 pub use equivalent::Equivalent;
+#[cfg(feature = "guard-tracing")]
+pub use guard_tracing::{set_guard_hold_hook, set_guard_hold_threshold};
 use hashbrown::hash_table;
-use iter::{Iter, IterMut, OwningIter};
+use iter::{Drain, Iter, IterMut, OwningIter};
 use lock::{RwLockReadGuardDetached, RwLockWriteGuardDetached};
-pub use mapref::entry::{Entry, OccupiedEntry, VacantEntry};
-use mapref::multiple::RefMulti;
+pub use map_diff::{Difference, IntoIter as MapDiffIter, MapDiff};
+pub use map_view::MapView;
+pub use mapref::batch_entry::{BatchEntry, BatchOccupiedEntry, BatchVacantEntry};
+pub use mapref::entry::{Entry, OccupiedEntry, OccupiedError, VacantEntry};
+use mapref::multiple::{RefMulti, RefMutMulti};
 use mapref::one::{Ref, RefMut};
+use mapref::owned::{OwnedEntry, OwnedOccupiedEntry, OwnedRef, OwnedRefMut, OwnedVacantEntry};
 pub use read_only::ReadOnlyView;
 pub use set::DashSet;
 use std::collections::hash_map::RandomState;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 use try_result::TryResult;
 
 pub(crate) type HashMap<K, V> = hash_table::HashTable<(K, V)>;
@@ -60,6 +72,18 @@ pub(crate) type HashMap<K, V> = hash_table::HashTable<(K, V)>;
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct TryReserveError {}
 
+/// What [`DashMap::upsert`] did to the map.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Upsert<V> {
+    /// The key was not present, and a new entry was inserted.
+    Inserted,
+    /// The key was present, and its value was updated.
+    Updated {
+        /// The value before the update.
+        old: V,
+    },
+}
+
 fn default_shard_amount() -> usize {
     static DEFAULT_SHARD_AMOUNT: OnceLock<usize> = OnceLock::new();
     *DEFAULT_SHARD_AMOUNT.get_or_init(|| {
@@ -67,6 +91,10 @@ fn default_shard_amount() -> usize {
     })
 }
 
+/// [`DashMap::retain_and_shrink`] shrinks a shard once its capacity is more than
+/// this many times the number of entries left in it.
+const RETAIN_SHRINK_RATIO: usize = 4;
+
 fn ncb(shard_amount: usize) -> usize {
     shard_amount.trailing_zeros() as usize
 }
@@ -84,8 +112,11 @@ fn ncb(shard_amount: usize) -> usize {
 /// This means that it is safe to ignore it across multiple threads.
 pub struct DashMap<K, V, S = RandomState> {
     shift: usize,
+    /// Whenever several shards are held at once, they are locked in ascending index order, so two
+    /// callers can not each end up waiting for a shard the other one holds.
     shards: Box<[CachePadded<RwLock<HashMap<K, V>>>]>,
     hasher: S,
+    loads: loading::Loads<K>,
 }
 
 impl<K: Eq + Hash + Clone, V: Clone, S: Clone> Clone for DashMap<K, V, S> {
@@ -98,6 +129,7 @@ impl<K: Eq + Hash + Clone, V: Clone, S: Clone> Clone for DashMap<K, V, S> {
             shift: self.shift,
             shards: self.shards.iter().map(clone_rwlock).collect(),
             hasher: self.hasher.clone(),
+            loads: Default::default(),
         }
     }
 }
@@ -144,8 +176,8 @@ impl<'a, K: 'a + Eq + Hash, V: 'a> DashMap<K, V, RandomState> {
 
     /// Creates a new DashMap with a specified shard amount
     ///
-    /// shard_amount should greater than 0 and be a power of two.
-    /// If a shard_amount which is not a power of two is provided, the function will panic.
+    /// shard_amount should be greater than 1 and a power of two.
+    /// If any other shard_amount is provided, the function will panic.
     ///
     /// # Examples
     ///
@@ -162,8 +194,8 @@ impl<'a, K: 'a + Eq + Hash, V: 'a> DashMap<K, V, RandomState> {
 
     /// Creates a new DashMap with a specified capacity and shard amount.
     ///
-    /// shard_amount should greater than 0 and be a power of two.
-    /// If a shard_amount which is not a power of two is provided, the function will panic.
+    /// shard_amount should be greater than 1 and a power of two.
+    /// If any other shard_amount is provided, the function will panic.
     ///
     /// # Examples
     ///
@@ -189,6 +221,166 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         ReadOnlyView::new(self)
     }
 
+    /// Read-locks every shard and returns a view of the whole map as it is at this point.
+    /// No other thread can modify the map until the view is dropped.
+    ///
+    /// Shards are always locked one by one in index order, so this can not deadlock
+    /// against another call to `lock_all_read` or against accessors that lock a single shard.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map,
+    /// and any write access to the map (such as `insert`, `remove` or `get_mut`)
+    /// from the same thread while the view is alive will deadlock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("apple", 3);
+    /// map.insert("pear", 5);
+    ///
+    /// let view = map.lock_all_read();
+    /// assert_eq!(view.len(), 2);
+    /// assert_eq!(view.get("pear"), Some(&5));
+    /// assert_eq!(view.iter().map(|(_k, v)| v).sum::<i32>(), 8);
+    /// ```
+    pub fn lock_all_read(&'a self) -> MapView<'a, K, V, S> {
+        MapView::new(self)
+    }
+
+    /// Clones the contents of the map into a standard `HashMap` using a clone of this map's hasher.
+    ///
+    /// The result is presized for the current number of entries, and each shard is read-locked once.
+    /// This is not atomic across shards, see [`lock_all_read`](DashMap::lock_all_read) for a consistent snapshot.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    ///
+    /// let snapshot = map.to_hashmap();
+    /// assert_eq!(snapshot.get("a"), Some(&1));
+    /// ```
+    pub fn to_hashmap(&self) -> std::collections::HashMap<K, V, S>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let mut map =
+            std::collections::HashMap::with_capacity_and_hasher(self.len(), self.hasher.clone());
+
+        for shard in self.shards.iter() {
+            map.extend(shard.read().iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+
+        map
+    }
+
+    /// Compares this map with `other`, and returns the entries only present in one of them
+    /// and the keys whose values differ, with clones of the keys and values involved.
+    ///
+    /// Each map is walked one shard at a time while the other one is probed, so no more than
+    /// one shard of each map is locked at once. This is not atomic: if either map is modified
+    /// concurrently, the result is unspecified.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into either map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::{DashMap, Difference};
+    ///
+    /// let before = DashMap::new();
+    /// before.insert("a", 1);
+    /// before.insert("b", 2);
+    /// let after = DashMap::new();
+    /// after.insert("b", 3);
+    ///
+    /// let diff = before.diff(&after);
+    /// assert_eq!(diff.only_in_self, [("a", 1)]);
+    /// assert!(diff.only_in_other.is_empty());
+    /// assert_eq!(diff.changed, [("b", 2, 3)]);
+    ///
+    /// let differences: Vec<_> = after.diff(&before).into_iter().collect();
+    /// assert!(differences.contains(&Difference::OnlyInOther("a", 1)));
+    /// ```
+    pub fn diff<T: BuildHasher + Clone>(&self, other: &DashMap<K, V, T>) -> MapDiff<K, V>
+    where
+        K: Clone,
+        V: Clone + PartialEq,
+    {
+        let mut only_in_self = Vec::new();
+        let mut changed = Vec::new();
+
+        for shard in self.shards.iter() {
+            for (k, v) in shard.read().iter() {
+                match other.get(k) {
+                    None => only_in_self.push((k.clone(), v.clone())),
+                    Some(theirs) if *theirs != *v => {
+                        changed.push((k.clone(), v.clone(), theirs.value().clone()))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let mut only_in_other = Vec::new();
+
+        for shard in other.shards.iter() {
+            for (k, v) in shard.read().iter() {
+                if !self.contains_key(k) {
+                    only_in_other.push((k.clone(), v.clone()));
+                }
+            }
+        }
+
+        MapDiff {
+            only_in_self,
+            only_in_other,
+            changed,
+        }
+    }
+
+    /// Returns `true` if both maps have the same length and every key maps to an equal value in both.
+    /// Unlike [`diff`](DashMap::diff), this stops at the first difference and clones nothing.
+    ///
+    /// This is the same comparison as `==`, but `other` may use a different hasher.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into either map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let a = DashMap::new();
+    /// a.insert("Johnny", 21);
+    /// let b = DashMap::with_hasher(RandomState::new());
+    /// b.insert("Johnny", 21);
+    /// assert!(a.is_identical_to(&b));
+    ///
+    /// b.insert("Johnny", 22);
+    /// assert!(!a.is_identical_to(&b));
+    /// ```
+    pub fn is_identical_to<T: BuildHasher + Clone>(&self, other: &DashMap<K, V, T>) -> bool
+    where
+        V: PartialEq,
+    {
+        self.len() == other.len()
+            && self.iter().all(|r| {
+                other
+                    .get(r.key())
+                    .map_or(false, |theirs| *r.value() == *theirs)
+            })
+    }
+
     /// Creates a new DashMap with a capacity of 0 and the provided hasher.
     ///
     /// # Examples
@@ -224,8 +416,8 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
 
     /// Creates a new DashMap with a specified hasher and shard amount
     ///
-    /// shard_amount should be greater than 0 and a power of two.
-    /// If a shard_amount which is not a power of two is provided, the function will panic.
+    /// shard_amount should be greater than 1 and a power of two.
+    /// If any other shard_amount is provided, the function will panic.
     ///
     /// # Examples
     ///
@@ -244,8 +436,8 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
 
     /// Creates a new DashMap with a specified starting capacity, hasher and shard_amount.
     ///
-    /// shard_amount should greater than 0 and be a power of two.
-    /// If a shard_amount which is not a power of two is provided, the function will panic.
+    /// shard_amount should be greater than 1 and a power of two.
+    /// If any other shard_amount is provided, the function will panic.
     ///
     /// # Examples
     ///
@@ -263,8 +455,14 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         hasher: S,
         shard_amount: usize,
     ) -> Self {
-        assert!(shard_amount > 1);
-        assert!(shard_amount.is_power_of_two());
+        assert!(
+            shard_amount > 1,
+            "shard amount must be greater than 1, got {shard_amount}"
+        );
+        assert!(
+            shard_amount.is_power_of_two(),
+            "shard amount must be a power of two, got {shard_amount}"
+        );
 
         let shift = util::ptr_size_bits() - ncb(shard_amount);
 
@@ -282,6 +480,7 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
             shift,
             shards,
             hasher,
+            loads: Default::default(),
         }
     }
 
@@ -291,6 +490,40 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         self.hash_u64(item) as usize
     }
 
+    /// Returns the number of shards in the map, which is always a power of two.
+    ///
+    /// The number of shards is fixed when the map is created and does not change afterwards.
+    /// This does not lock anything.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map: DashMap<i32, i32> = DashMap::with_shard_amount(16);
+    /// assert_eq!(map.shard_count(), 16);
+    /// ```
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Hashes a key with the map's hasher, for use with the `_hashed` methods such as
+    /// [`get_hashed`](DashMap::get_hashed).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// let hash = map.hash_key("apple");
+    /// map.insert_hashed(hash, "apple", 3);
+    /// assert_eq!(*map.get_hashed(hash, "apple").unwrap(), 3);
+    /// ```
+    pub fn hash_key<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.hash_u64(&key)
+    }
+
     fn hash_u64<T: Hash>(&self, item: &T) -> u64 {
         let mut hasher = self.hasher.build_hasher();
 
@@ -379,6 +612,9 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
             /// You should probably not use this unless you know what you are doing.
             /// Note that shard selection is dependent on the default or provided HashBuilder.
             ///
+            /// This uses the same hashing and shard selection as every lookup, so the returned
+            /// index can be used to group keys by shard before locking [`DashMap::shards`].
+            ///
             /// Requires the `raw-api` feature to be enabled.
             ///
             /// # Examples
@@ -389,6 +625,9 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
             /// let map = DashMap::new();
             /// map.insert("coca-cola", 1.4);
             /// println!("coca-cola is stored in shard: {}", map.determine_map("coca-cola"));
+            ///
+            /// let shard = map.shards()[map.determine_map("coca-cola")].read();
+            /// assert_eq!(shard.iter().filter(|(k, _v)| *k == "coca-cola").count(), 1);
             /// ```
             pub fn determine_map<Q>(&self, key: &Q) -> usize
             where
@@ -400,65 +639,60 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         }
     }
 
-    cfg_if! {
-        if #[cfg(feature = "raw-api")] {
-            /// Finds which shard a certain hash is stored in.
-            ///
-            /// Requires the `raw-api` feature to be enabled.
-            ///
-            /// # Examples
-            ///
-            /// ```
-            /// use dashmap::DashMap;
-            ///
-            /// let map: DashMap<i32, i32> = DashMap::new();
-            /// let key = "key";
-            /// let hash = map.hash_usize(&key);
-            /// println!("hash is stored in shard: {}", map.determine_shard(hash));
-            /// ```
-            pub fn determine_shard(&self, hash: usize) -> usize {
-                // Leave the high 7 bits for the HashBrown SIMD tag.
-                let idx = (hash << 7) >> self.shift;
-
-                // hint to llvm that the panic bounds check can be removed
-                if idx >= self.shards.len() {
-                    if cfg!(debug_assertions) {
-                        unreachable!("invalid shard index")
-                    } else {
-                        // SAFETY: shards is always a power of two,
-                        // and shift is calculated such that the resulting idx is always
-                        // less than the shards length
-                        unsafe {
-                            std::hint::unreachable_unchecked();
-                        }
-                    }
-                }
-
-                idx
-            }
-        } else {
-
-            pub(crate) fn determine_shard(&self, hash: usize) -> usize {
-                // Leave the high 7 bits for the HashBrown SIMD tag.
-                let idx = (hash << 7) >> self.shift;
+    /// Finds which shard a certain key is stored in.
+    ///
+    /// This uses the same hashing and shard selection as every lookup, so the returned index can
+    /// be used to group keys by shard, for instance to compare with [`shard_lengths`](DashMap::shard_lengths)
+    /// or to find out which keys contend for the same lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map: DashMap<&str, i32> = DashMap::with_shard_amount(4);
+    /// let idx = map.shard_index_of("key");
+    /// assert!(idx < 4);
+    /// assert_eq!(idx, map.determine_shard(map.hash_usize(&"key")));
+    /// ```
+    pub fn shard_index_of<Q>(&self, key: &Q) -> usize
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self.determine_shard(self.hash_usize(&key))
+    }
 
-                // hint to llvm that the panic bounds check can be removed
-                if idx >= self.shards.len() {
-                    if cfg!(debug_assertions) {
-                        unreachable!("invalid shard index")
-                    } else {
-                        // SAFETY: shards is always a power of two,
-                        // and shift is calculated such that the resulting idx is always
-                        // less than the shards length
-                        unsafe {
-                            std::hint::unreachable_unchecked();
-                        }
-                    }
+    /// Finds which shard a certain hash is stored in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map: DashMap<i32, i32> = DashMap::new();
+    /// let key = "key";
+    /// let hash = map.hash_usize(&key);
+    /// println!("hash is stored in shard: {}", map.determine_shard(hash));
+    /// ```
+    pub fn determine_shard(&self, hash: usize) -> usize {
+        // Leave the high 7 bits for the HashBrown SIMD tag.
+        let idx = (hash << 7) >> self.shift;
+
+        // hint to llvm that the panic bounds check can be removed
+        if idx >= self.shards.len() {
+            if cfg!(debug_assertions) {
+                unreachable!("invalid shard index")
+            } else {
+                // SAFETY: shards is always a power of two,
+                // and shift is calculated such that the resulting idx is always
+                // less than the shards length
+                unsafe {
+                    std::hint::unreachable_unchecked();
                 }
-
-                idx
             }
         }
+
+        idx
     }
 
     /// Returns a reference to the map's [`BuildHasher`].
@@ -496,6 +730,168 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         self._insert(key, value)
     }
 
+    /// Like [`insert`](DashMap::insert), but also returns a mutable reference to the stored value,
+    /// so it can be used right away without looking the key up again.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// let (old, mut value) = map.insert_and_get("config", vec![1]);
+    /// assert_eq!(old, None);
+    /// value.push(2);
+    /// drop(value);
+    ///
+    /// let (old, value) = map.insert_and_get("config", vec![3]);
+    /// assert_eq!(old, Some(vec![1, 2]));
+    /// assert_eq!(*value, [3]);
+    /// ```
+    pub fn insert_and_get(&'a self, key: K, value: V) -> (Option<V>, RefMut<'a, K, V>) {
+        self._insert_and_get(key, value)
+    }
+
+    /// Like [`insert_and_get`](DashMap::insert_and_get), but the shard lock is downgraded
+    /// and a shared reference is returned.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn insert_and_get_ref(&'a self, key: K, value: V) -> (Option<V>, Ref<'a, K, V>) {
+        self._insert_and_get_ref(key, value)
+    }
+
+    /// Inserts every key-value pair yielded by the iterator into the map, locking each shard at most once.
+    /// Returns how many of the inserted keys were already present. Their values are replaced,
+    /// while the keys stored in the map are kept, like [`insert`](DashMap::insert) does.
+    ///
+    /// The pairs are grouped by shard before any lock is taken. If the iterator yields the same key
+    /// more than once, the value yielded last ends up in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("a", 0);
+    /// assert_eq!(map.insert_many([("a", 1), ("b", 2), ("b", 3)]), 2);
+    /// assert_eq!(*map.get("a").unwrap(), 1);
+    /// assert_eq!(*map.get("b").unwrap(), 3);
+    /// ```
+    pub fn insert_many<I: IntoIterator<Item = (K, V)>>(&self, iter: I) -> usize {
+        self._insert_many(iter)
+    }
+
+    /// Moves every entry of `other` into this map, calling `resolve` with the key, the value in this map
+    /// and the value from `other` for keys present in both, and storing the value it returns.
+    ///
+    /// `other` is consumed one shard at a time. The entries of each of its shards are grouped by
+    /// the shard they belong to in this map, so each shard of this map is locked at most once per
+    /// shard of `other`. `resolve` is called while the shard is locked.
+    ///
+    /// If `resolve` panics, the key it was called for is removed from this map, along with both
+    /// values, and the entries of `other` not merged yet are dropped.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map,
+    /// or if `resolve` accesses the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let totals = DashMap::new();
+    /// totals.insert("a", 1);
+    /// let batch = DashMap::new();
+    /// batch.insert("a", 10);
+    /// batch.insert("b", 20);
+    ///
+    /// totals.merge_from(batch, |_, old, new| old + new);
+    /// assert_eq!(*totals.get("a").unwrap(), 11);
+    /// assert_eq!(*totals.get("b").unwrap(), 20);
+    /// ```
+    pub fn merge_from<T>(&self, other: DashMap<K, V, T>, mut resolve: impl FnMut(&K, V, V) -> V) {
+        for shard in other.shards.into_vec() {
+            self.merge_entries(shard.into_inner().into_inner(), &mut resolve);
+        }
+    }
+
+    /// Like [`merge_from`](DashMap::merge_from), but clones the entries of `other` instead of
+    /// moving them, leaving it untouched.
+    ///
+    /// Each shard of `other` is unlocked before its entries are merged into this map.
+    /// If `resolve` panics, the key it was called for is removed from this map, but `other`
+    /// is left untouched.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into `other`,
+    /// any sort of reference into this map, or if `resolve` accesses either map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let totals = DashMap::new();
+    /// totals.insert("a", 1);
+    /// let batch = DashMap::new();
+    /// batch.insert("a", 10);
+    ///
+    /// totals.merge_from_ref(&batch, |_, old, new| old.max(new));
+    /// assert_eq!(*totals.get("a").unwrap(), 10);
+    /// assert_eq!(batch.len(), 1);
+    /// ```
+    pub fn merge_from_ref<T>(
+        &self,
+        other: &DashMap<K, V, T>,
+        mut resolve: impl FnMut(&K, V, V) -> V,
+    ) where
+        K: Clone,
+        V: Clone,
+    {
+        for shard in other.shards.iter() {
+            let entries: Vec<_> = shard
+                .read()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            self.merge_entries(entries, &mut resolve);
+        }
+    }
+
+    /// Calls `f` with the entry for every key yielded by the iterator, locking each shard at most once.
+    ///
+    /// The keys are grouped by shard before any lock is taken. Shards are then visited in index order,
+    /// and within a shard the keys are visited in the order they were yielded. The shard stays
+    /// write-locked while `f` runs for all of its keys, and each [`BatchEntry`] borrows it from there
+    /// instead of holding a lock of its own.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map,
+    /// or if `f` accesses the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let totals = DashMap::new();
+    /// totals.insert("a", 10);
+    ///
+    /// totals.entry_many(["a", "b", "a"], |entry| *entry.or_insert(0) += 1);
+    /// assert_eq!(*totals.get("a").unwrap(), 12);
+    /// assert_eq!(*totals.get("b").unwrap(), 1);
+    /// ```
+    pub fn entry_many<I: IntoIterator<Item = K>>(
+        &self,
+        keys: I,
+        f: impl FnMut(BatchEntry<'_, K, V>),
+    ) {
+        self._entry_many(keys, f)
+    }
+
     /// Removes an entry from the map, returning the key and value if they existed in the map.
     ///
     /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
@@ -544,6 +940,28 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         self._remove_if(key, f)
     }
 
+    /// Removes an entry from the map, returning the key and value
+    /// if the entry existed and the provided conditional function returned true.
+    ///
+    /// Unlike [`remove_if`](Self::remove_if), the function gets mutable access to the value.
+    /// Changes it makes are kept even if it returns false and the entry stays in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let refcounts = DashMap::new();
+    /// refcounts.insert("Sam", 2);
+    /// let release = |_: &&str, count: &mut i32| {
+    ///     *count -= 1;
+    ///     *count == 0
+    /// };
+    /// assert!(refcounts.remove_if_mut("Sam", release).is_none());
+    /// assert_eq!(*refcounts.get("Sam").unwrap(), 1);
+    /// assert_eq!(refcounts.remove_if_mut("Sam", release), Some(("Sam", 0)));
+    /// assert!(!refcounts.contains_key("Sam"));
+    /// ```
     pub fn remove_if_mut<Q>(&self, key: &Q, f: impl FnOnce(&K, &mut V) -> bool) -> Option<(K, V)>
     where
         Q: Hash + Equivalent<K> + ?Sized,
@@ -586,7 +1004,10 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         self._iter_mut()
     }
 
-    /// Get an immutable reference to an entry in the map
+    /// Looks up several keys at once, yielding one result per key in the order they were given.
+    ///
+    /// The keys are grouped by shard, so each shard involved is read-locked once rather than once
+    /// per key. Shards with matching entries stay read-locked until the references into them are dropped.
     ///
     /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
     ///
@@ -595,40 +1016,234 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let youtubers = DashMap::new();
-    /// youtubers.insert("Bosnian Bill", 457000);
-    /// assert_eq!(*youtubers.get("Bosnian Bill").unwrap(), 457000);
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let values: Vec<_> = map
+    ///     .multi_get(["b", "missing", "a"].iter())
+    ///     .map(|r| r.map(|r| *r.value()))
+    ///     .collect();
+    /// assert_eq!(values, [Some(2), None, Some(1)]);
     /// ```
-    pub fn get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
+    pub fn multi_get<'k, Q, I>(
+        &'a self,
+        keys: I,
+    ) -> impl Iterator<Item = Option<RefMulti<'a, K, V>>>
     where
-        Q: Hash + Equivalent<K> + ?Sized,
+        Q: Hash + Equivalent<K> + ?Sized + 'k,
+        I: IntoIterator<Item = &'k Q>,
     {
-        self._get(key)
+        self._multi_get(keys)
     }
 
-    /// Get a mutable reference to an entry in the map
+    /// Like [`multi_get`](DashMap::multi_get), but for a fixed number of keys.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let class = DashMap::new();
-    /// class.insert("Albin", 15);
-    /// *class.get_mut("Albin").unwrap() -= 1;
-    /// assert_eq!(*class.get("Albin").unwrap(), 14);
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    ///
+    /// let [a, b] = map.get_many(["a", "b"]);
+    /// assert_eq!(*a.unwrap(), 1);
+    /// assert!(b.is_none());
     /// ```
-    pub fn get_mut<Q>(&'a self, key: &Q) -> Option<RefMut<'a, K, V>>
+    pub fn get_many<Q, const N: usize>(&'a self, keys: [&Q; N]) -> [Option<RefMulti<'a, K, V>>; N]
     where
         Q: Hash + Equivalent<K> + ?Sized,
     {
-        self._get_mut(key)
+        self._get_many(keys)
     }
 
-    /// Get an immutable reference to an entry in the map, if the shard is not locked.
-    /// If the shard is locked, the function will return [TryResult::Locked].
+    /// Like [`get_many`](DashMap::get_many), but clones the values so that no lock is held
+    /// after returning.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    /// assert_eq!(map.get_many_cloned(["a", "b"]), [Some(1), None]);
+    /// ```
+    pub fn get_many_cloned<Q, const N: usize>(&self, keys: [&Q; N]) -> [Option<V>; N]
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+        V: Clone,
+    {
+        self._get_many_cloned(keys)
+    }
+
+    /// Returns mutable references to the values of several distinct keys at once.
+    ///
+    /// Returns None if any of the keys is not present, or if two of them refer to the same entry.
+    /// The shards involved are write-locked in index order, and stay locked until all the references
+    /// into them are dropped. Keys in the same shard share a single lock.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let accounts = DashMap::new();
+    /// accounts.insert("alice", 100);
+    /// accounts.insert("bob", 50);
+    ///
+    /// let [mut from, mut to] = accounts.get_many_mut(["alice", "bob"]).unwrap();
+    /// *from -= 30;
+    /// *to += 30;
+    /// drop((from, to));
+    ///
+    /// assert_eq!(*accounts.get("alice").unwrap(), 70);
+    /// assert_eq!(*accounts.get("bob").unwrap(), 80);
+    /// assert!(accounts.get_many_mut(["alice", "alice"]).is_none());
+    /// assert!(accounts.get_many_mut(["alice", "carol"]).is_none());
+    /// ```
+    pub fn get_many_mut<Q, const N: usize>(
+        &'a self,
+        keys: [&Q; N],
+    ) -> Option<[RefMutMulti<'a, K, V>; N]>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._get_many_mut(keys)
+    }
+
+    /// Returns the entries whose keys fall into `range`, sorted by key.
+    ///
+    /// The map has no order of its own, so this scans every entry and sorts the `m` matching ones,
+    /// which takes `O(n + m log m)` time for a map with `n` entries. The result is a snapshot:
+    /// shards with matching entries stay read-locked until the references to them are dropped,
+    /// but entries inserted into other shards in the meantime are not included.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// for i in 0..100 {
+    ///     map.insert(i, i * i);
+    /// }
+    ///
+    /// let squares: Vec<_> = map.range(10..13).map(|r| *r.value()).collect();
+    /// assert_eq!(squares, [100, 121, 144]);
+    /// ```
+    pub fn range<R>(&'a self, range: R) -> impl Iterator<Item = RefMulti<'a, K, V>>
+    where
+        K: Ord,
+        R: RangeBounds<K>,
+    {
+        self._range(range)
+    }
+
+    /// Get an immutable reference to an entry in the map
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let youtubers = DashMap::new();
+    /// youtubers.insert("Bosnian Bill", 457000);
+    /// assert_eq!(*youtubers.get("Bosnian Bill").unwrap(), 457000);
+    /// ```
+    pub fn get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._get(key)
+    }
+
+    /// Get an immutable reference to an entry in a map shared through an [`Arc`].
+    ///
+    /// The returned [`OwnedRef`] holds a clone of the `Arc` instead of borrowing the map, so it
+    /// can be returned from functions, moved into other threads or held across `.await` points.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::sync::Arc;
+    ///
+    /// let youtubers = Arc::new(DashMap::new());
+    /// youtubers.insert("Bosnian Bill", 457000);
+    /// let subscribers = youtubers.get_owned("Bosnian Bill").unwrap();
+    /// drop(youtubers);
+    /// assert_eq!(*subscribers, 457000);
+    /// ```
+    pub fn get_owned<Q>(self: &Arc<Self>, key: &Q) -> Option<OwnedRef<K, V, S>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._get_owned(key)
+    }
+
+    /// Get a mutable reference to an entry in the map
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let class = DashMap::new();
+    /// class.insert("Albin", 15);
+    /// *class.get_mut("Albin").unwrap() -= 1;
+    /// assert_eq!(*class.get("Albin").unwrap(), 14);
+    /// ```
+    pub fn get_mut<Q>(&'a self, key: &Q) -> Option<RefMut<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._get_mut(key)
+    }
+
+    /// Get a mutable reference to an entry in a map shared through an [`Arc`].
+    ///
+    /// See [`DashMap::get_owned`]. The returned [`OwnedRefMut`] keeps the shard write-locked,
+    /// so holding it across an `.await` point stalls every other user of that shard.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::sync::Arc;
+    ///
+    /// let class = Arc::new(DashMap::new());
+    /// class.insert("Albin", 15);
+    /// let mut albin = class.get_mut_owned("Albin").unwrap();
+    /// std::thread::spawn(move || *albin -= 1).join().unwrap();
+    /// assert_eq!(*class.get("Albin").unwrap(), 14);
+    /// ```
+    pub fn get_mut_owned<Q>(self: &Arc<Self>, key: &Q) -> Option<OwnedRefMut<K, V, S>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._get_mut_owned(key)
+    }
+
+    /// Get an immutable reference to an entry in the map, if the shard is not locked.
+    /// If the shard is locked, the function will return [TryResult::Locked].
     ///
     /// # Examples
     ///
@@ -680,134 +1295,176 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         self._try_get_mut(key)
     }
 
-    /// Remove excess capacity to reduce memory usage.
+    /// Like [`try_get`](DashMap::try_get), but waits up to `timeout` for the shard to be unlocked
+    /// before returning [TryResult::Locked].
+    ///
+    /// **Locking behaviour:** Waits at most `timeout`, even when holding a reference into the map.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
-    /// use dashmap::try_result::TryResult;
+    /// use std::time::Duration;
     ///
     /// let map = DashMap::new();
     /// map.insert("Johnny", 21);
-    /// assert!(map.capacity() > 0);
-    /// map.remove("Johnny");
-    /// map.shrink_to_fit();
-    /// assert_eq!(map.capacity(), 0);
+    ///
+    /// let guard = map.get_mut("Johnny").unwrap();
+    /// assert!(map.get_timeout("Johnny", Duration::from_millis(1)).is_locked());
+    /// drop(guard);
+    ///
+    /// assert_eq!(*map.get_timeout("Johnny", Duration::from_millis(1)).unwrap(), 21);
     /// ```
-    pub fn shrink_to_fit(&self) {
-        self._shrink_to_fit();
+    pub fn get_timeout<Q>(&'a self, key: &Q, timeout: Duration) -> TryResult<Ref<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._get_timeout(key, timeout)
     }
 
-    /// Retain elements that whose predicates return true
-    /// and discard elements whose predicates return false.
+    /// Like [`try_get_mut`](DashMap::try_get_mut), but waits up to `timeout` for the shard to be unlocked
+    /// before returning [TryResult::Locked].
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// **Locking behaviour:** Waits at most `timeout`, even when holding a reference into the map.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
+    /// use std::time::Duration;
     ///
-    /// let people = DashMap::new();
-    /// people.insert("Albin", 15);
-    /// people.insert("Jones", 22);
-    /// people.insert("Charlie", 27);
-    /// people.retain(|_, v| *v > 20);
-    /// assert_eq!(people.len(), 2);
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", 21);
+    ///
+    /// let guard = map.get("Johnny").unwrap();
+    /// assert!(map.get_mut_timeout("Johnny", Duration::from_millis(1)).is_locked());
+    /// drop(guard);
+    ///
+    /// *map.get_mut_timeout("Johnny", Duration::from_millis(1)).unwrap() += 1;
+    /// assert_eq!(*map.get("Johnny").unwrap(), 22);
     /// ```
-    pub fn retain(&self, f: impl FnMut(&K, &mut V) -> bool) {
-        self._retain(f);
+    pub fn get_mut_timeout<Q>(&'a self, key: &Q, timeout: Duration) -> TryResult<RefMut<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._get_mut_timeout(key, timeout)
     }
 
-    /// Fetches the total number of key-value pairs stored in the map.
+    /// Get a reference to an entry in the map, inserting `default` first if the key is not present.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    /// If the key is present, the shard is only read-locked, so this never waits for or blocks
+    /// other readers. Otherwise the shard is write-locked to insert `default`, and the lock is
+    /// downgraded before returning. Note that `default` is always constructed by the caller,
+    /// even if the key is present and it ends up being dropped.
+    /// If it is expensive to build, use [`DashMap::get_or_insert_with`] instead.
+    ///
+    /// To modify the value afterwards, use [`DashMap::get_mut_or_insert`].
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map,
+    /// or any sort of reference if the key is absent.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let people = DashMap::new();
-    /// people.insert("Albin", 15);
-    /// people.insert("Jones", 22);
-    /// people.insert("Charlie", 27);
-    /// assert_eq!(people.len(), 3);
+    /// let names = DashMap::new();
+    /// assert_eq!(*names.get_or_insert(1, "one"), "one");
+    /// assert_eq!(*names.get_or_insert(1, "uno"), "one");
     /// ```
-    pub fn len(&self) -> usize {
-        self._len()
+    pub fn get_or_insert(&'a self, key: K, default: V) -> Ref<'a, K, V> {
+        self._get_or_insert(key, default)
     }
 
-    /// Checks if the map is empty or not.
+    /// Get a reference to an entry in the map,
+    /// inserting the result of `f` first if the key is not present.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    /// If the key is present, the shard is only read-locked, so this never waits for or blocks
+    /// other readers. Otherwise the shard is write-locked, `f` is called if the key is still absent,
+    /// and the lock is downgraded before returning. `f` runs at most once per inserted key,
+    /// even if several threads race to insert it.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map,
+    /// or any sort of reference if the key is absent.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let map = DashMap::<(), ()>::new();
-    /// assert!(map.is_empty());
+    /// let squares = DashMap::new();
+    /// assert_eq!(*squares.get_or_insert_with(12, || 12 * 12), 144);
+    /// assert_eq!(*squares.get_or_insert_with(12, || unreachable!()), 144);
     /// ```
-    pub fn is_empty(&self) -> bool {
-        self._is_empty()
+    pub fn get_or_insert_with(&'a self, key: K, f: impl FnOnce() -> V) -> Ref<'a, K, V> {
+        self._get_or_insert_with(key, f)
     }
 
-    /// Removes all key-value pairs in the map.
+    /// Get a reference to an entry in the map, inserting the result of `init` if the key is not present.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// Unlike [`get_or_insert_with`](DashMap::get_or_insert_with), `init` runs without holding
+    /// any lock, and at most one caller at a time runs it for a given key: if several threads
+    /// miss the same key concurrently, one of them runs `init` while the others wait for it and
+    /// then return the value it inserted. If `init` panics, one of the waiting callers runs its
+    /// own `init` instead.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map,
+    /// or if `init` calls this method for the same key.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let stats = DashMap::new();
-    /// stats.insert("Goals", 4);
-    /// assert!(!stats.is_empty());
-    /// stats.clear();
-    /// assert!(stats.is_empty());
+    /// let cache = DashMap::new();
+    /// assert_eq!(*cache.get_with("page", || String::from("<html>")), "<html>");
+    /// assert_eq!(*cache.get_with("page", || unreachable!()), "<html>");
     /// ```
-    pub fn clear(&self) {
-        self._clear();
+    pub fn get_with(&'a self, key: K, init: impl FnOnce() -> V) -> Ref<'a, K, V> {
+        match self._try_get_with(key, || Ok::<_, core::convert::Infallible>(init())) {
+            Ok(r) => r,
+            Err(err) => match *err {},
+        }
     }
 
-    /// Returns how many key-value pairs the map can store without reallocating.
+    /// Like [`get_with`](DashMap::get_with), but for an initializer that can fail.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
-    pub fn capacity(&self) -> usize {
-        self._capacity()
-    }
-
-    /// Modify a specific value according to a function.
+    /// If `init` fails, nothing is inserted, and the error is returned both to this caller
+    /// and to every caller that was waiting for it. The next call for the key runs `init` again.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map,
+    /// or if `init` calls this method for the same key.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let stats = DashMap::new();
-    /// stats.insert("Goals", 4);
-    /// stats.alter("Goals", |_, v| v * 2);
-    /// assert_eq!(*stats.get("Goals").unwrap(), 8);
-    /// ```
-    ///
-    /// # Panics
+    /// let cache = DashMap::new();
+    /// let res = cache.try_get_with("config", || "not found".parse::<u32>());
+    /// assert!(res.is_err());
+    /// assert!(!cache.contains_key("config"));
     ///
-    /// If the given closure panics, then `alter` will abort the process
-    pub fn alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V)
+    /// let res = cache.try_get_with("config", || "42".parse::<u32>());
+    /// assert_eq!(*res.unwrap(), 42);
+    /// ```
+    pub fn try_get_with<E>(
+        &'a self,
+        key: K,
+        init: impl FnOnce() -> Result<V, E>,
+    ) -> Result<Ref<'a, K, V>, Arc<E>>
     where
-        Q: Hash + Equivalent<K> + ?Sized,
+        E: Send + Sync + 'static,
     {
-        self._alter(key, f);
+        self._try_get_with(key, init)
     }
 
-    /// Modify every value in the map according to a function.
+    /// Get a mutable reference to an entry in the map,
+    /// inserting `default` first if the key is not present.
+    ///
+    /// The key is hashed once and the shard is write-locked once. Note that `default` is always
+    /// constructed by the caller, even if the key is present and it ends up being dropped.
+    /// If it is expensive to build, use [`DashMap::get_mut_or_insert_with`] instead.
     ///
     /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
     ///
@@ -816,22 +1473,19 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let stats = DashMap::new();
-    /// stats.insert("Wins", 4);
-    /// stats.insert("Losses", 2);
-    /// stats.alter_all(|_, v| v + 1);
-    /// assert_eq!(*stats.get("Wins").unwrap(), 5);
-    /// assert_eq!(*stats.get("Losses").unwrap(), 3);
+    /// let stock = DashMap::new();
+    /// *stock.get_mut_or_insert("apples", 0) += 4;
+    /// *stock.get_mut_or_insert("apples", 0) += 2;
+    /// assert_eq!(*stock.get("apples").unwrap(), 6);
     /// ```
-    ///
-    /// # Panics
-    ///
-    /// If the given closure panics, then `alter_all` will abort the process
-    pub fn alter_all(&self, f: impl FnMut(&K, V) -> V) {
-        self._alter_all(f);
+    pub fn get_mut_or_insert(&'a self, key: K, default: V) -> RefMut<'a, K, V> {
+        self._get_mut_or_insert(key, default)
     }
 
-    /// Scoped access into an item of the map according to a function.
+    /// Get a mutable reference to an entry in the map,
+    /// inserting the result of `f` first if the key is not present.
+    ///
+    /// The key is hashed once and the shard is write-locked once. `f` is only called if the key is absent.
     ///
     /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
     ///
@@ -840,745 +1494,4159 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let warehouse = DashMap::new();
-    /// warehouse.insert(4267, ("Banana", 100));
-    /// warehouse.insert(2359, ("Pear", 120));
-    /// let fruit = warehouse.view(&4267, |_k, v| *v);
-    /// assert_eq!(fruit, Some(("Banana", 100)));
+    /// let groups = DashMap::new();
+    /// groups.get_mut_or_insert_with("odd", Vec::new).push(1);
+    /// groups.get_mut_or_insert_with("odd", || unreachable!()).push(3);
+    /// assert_eq!(*groups.get("odd").unwrap(), [1, 3]);
     /// ```
-    ///
-    /// # Panics
-    ///
-    /// If the given closure panics, then `view` will abort the process
-    pub fn view<Q, R>(&self, key: &Q, f: impl FnOnce(&K, &V) -> R) -> Option<R>
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        self._view(key, f)
+    pub fn get_mut_or_insert_with(&'a self, key: K, f: impl FnOnce() -> V) -> RefMut<'a, K, V> {
+        self._get_mut_or_insert_with(key, f)
     }
 
-    /// Checks if the map contains a specific key.
+    /// Get a mutable reference to an entry in the map,
+    /// inserting the default value first if the key is not present.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let team_sizes = DashMap::new();
-    /// team_sizes.insert("Dakota Cherries", 23);
-    /// assert!(team_sizes.contains_key("Dakota Cherries"));
+    /// let words: DashMap<&str, u32> = DashMap::new();
+    /// for word in ["a", "b", "a"] {
+    ///     *words.get_mut_or_default(word) += 1;
+    /// }
+    /// assert_eq!(*words.get("a").unwrap(), 2);
     /// ```
-    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    pub fn get_mut_or_default(&'a self, key: K) -> RefMut<'a, K, V>
     where
-        Q: Hash + Equivalent<K> + ?Sized,
+        V: Default,
     {
-        self._contains_key(key)
+        self._get_mut_or_insert_with(key, V::default)
     }
 
-    /// Advanced entry API that tries to mimic `std::collections::HashMap`.
-    /// See the documentation on `dashmap::mapref::entry` for more details.
+    /// Inserts a key and a value into the map only if the key is not present yet.
+    ///
+    /// Returns None if the value was inserted, or gives the value back if the key already existed,
+    /// in which case the map is left untouched and the rejected key is dropped. Unlike
+    /// [`try_insert`](DashMap::try_insert), no reference into the map is kept after returning.
     ///
     /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
-    pub fn entry(&'a self, key: K) -> Entry<'a, K, V> {
-        self._entry(key)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let owners = DashMap::new();
+    /// assert_eq!(owners.insert_if_absent("job-1", "worker-a"), None);
+    /// assert_eq!(owners.insert_if_absent("job-1", "worker-b"), Some("worker-b"));
+    /// assert_eq!(*owners.get("job-1").unwrap(), "worker-a");
+    /// ```
+    pub fn insert_if_absent(&self, key: K, value: V) -> Option<V> {
+        self._insert_if_absent(key, value)
     }
 
-    /// Advanced entry API that tries to mimic `std::collections::HashMap`.
-    /// See the documentation on `dashmap::mapref::entry` for more details.
+    /// Replaces the value for `key` only if the key is already present, returning the old value.
     ///
-    /// Returns None if the shard is currently locked.
-    pub fn try_entry(&'a self, key: K) -> Option<Entry<'a, K, V>> {
-        self._try_entry(key)
+    /// If the key is not present, the map is left untouched and the value is handed back as the error.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let sessions = DashMap::new();
+    /// sessions.insert("alice", "token-1");
+    ///
+    /// assert_eq!(sessions.replace_existing("alice", "token-2"), Ok("token-1"));
+    /// assert_eq!(sessions.replace_existing("bob", "token-3"), Err("token-3"));
+    /// assert!(!sessions.contains_key("bob"));
+    /// ```
+    pub fn replace_existing<Q>(&self, key: &Q, value: V) -> Result<V, V>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._replace_existing(key, value)
     }
 
-    /// Advanced entry API that tries to mimic `hashbrown::HashMap::entry_ref`.
-    /// See the documentation on `dashmap::mapref::entry_ref` for more details.
+    /// Inserts a key and a value into the map only if the key is not present yet,
+    /// and returns a mutable reference to the inserted value.
+    ///
+    /// If the key already exists, nothing is updated and an error containing the occupied entry
+    /// and the rejected value is returned. Checking and inserting happen under a single shard lock,
+    /// so no other thread can insert the key in between.
     ///
     /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
-    pub fn entry_ref<'q, Q>(&'a self, key: &'q Q) -> EntryRef<'a, 'q, K, Q, V>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let owners = DashMap::new();
+    /// assert_eq!(*owners.try_insert("job-1", "worker-a").unwrap(), "worker-a");
+    ///
+    /// let err = owners.try_insert("job-1", "worker-b").unwrap_err();
+    /// assert_eq!(*err.entry().get(), "worker-a");
+    /// assert_eq!(*err.value(), "worker-b");
+    /// ```
+    pub fn try_insert(
+        &'a self,
+        key: K,
+        value: V,
+    ) -> Result<RefMut<'a, K, V>, OccupiedError<'a, K, V>> {
+        self._try_insert(key, value)
+    }
+
+    /// Inserts a key and a value into the map, and reports whether the key was new
+    /// or an existing value was replaced, along with the replaced value.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::{DashMap, Upsert};
+    ///
+    /// let last_seen = DashMap::new();
+    /// assert_eq!(last_seen.upsert("alice", 1), Upsert::Inserted);
+    /// assert_eq!(last_seen.upsert("alice", 5), Upsert::Updated { old: 1 });
+    /// ```
+    pub fn upsert(&self, key: K, value: V) -> Upsert<V> {
+        self._upsert(key, value)
+    }
+
+    /// Inserts the result of `insert` if the key is not present, or calls `update` on the existing value.
+    /// Returns true if the key was already present and its value was updated.
+    ///
+    /// Only one of the closures is called, while the shard is locked.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let visits = DashMap::new();
+    /// assert!(!visits.upsert_with("alice", || 1, |n| *n += 1));
+    /// assert!(visits.upsert_with("alice", || 1, |n| *n += 1));
+    /// assert_eq!(*visits.get("alice").unwrap(), 2);
+    /// ```
+    pub fn upsert_with(
+        &self,
+        key: K,
+        insert: impl FnOnce() -> V,
+        update: impl FnOnce(&mut V),
+    ) -> bool {
+        self._upsert_with(key, insert, update)
+    }
+
+    /// Calls `modify` on the value for `key` if it is present, or inserts the result of `insert` otherwise.
+    /// Returns true if the key was already present.
+    ///
+    /// Exactly one of the closures is called, while the shard is locked. Both are given the key,
+    /// which is only stored in the map if `insert` is called.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let words = DashMap::new();
+    /// for word in ["the", "cat", "the"] {
+    ///     words.modify_or_insert(word, |_, n| *n += 1, |_| 1);
+    /// }
+    /// assert_eq!(*words.get("the").unwrap(), 2);
+    /// assert_eq!(*words.get("cat").unwrap(), 1);
+    /// ```
+    pub fn modify_or_insert(
+        &self,
+        key: K,
+        modify: impl FnOnce(&K, &mut V),
+        insert: impl FnOnce(&K) -> V,
+    ) -> bool {
+        self._modify_or_insert(key, modify, insert)
+    }
+
+    /// Replaces the value for `key` with `new`, but only if it is equal to `current`.
+    ///
+    /// On failure, a clone of the actual value is returned, or None if the key is not present.
+    /// The comparison and the replacement happen under a single shard lock.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let states = DashMap::new();
+    /// states.insert("job", "queued");
+    ///
+    /// assert_eq!(states.compare_and_swap("job", &"queued", "running"), Ok(()));
+    /// assert_eq!(states.compare_and_swap("job", &"queued", "running"), Err(Some("running")));
+    /// assert_eq!(states.compare_and_swap("other", &"queued", "running"), Err(None));
+    /// ```
+    pub fn compare_and_swap<Q>(&self, key: &Q, current: &V, new: V) -> Result<(), Option<V>>
     where
-        Q: Hash + Equivalent<K>,
+        Q: Hash + Equivalent<K> + ?Sized,
+        V: PartialEq + Clone,
     {
-        self._entry_ref(key)
+        self._compare_exchange(key, current, new).map(drop)
     }
 
-    /// Advanced entry API that tries to mimic `std::collections::HashMap::try_reserve`.
-    /// Tries to reserve capacity for at least `shard * additional`
-    /// and may reserve more space to avoid frequent reallocations.
+    /// Like [`compare_and_swap`](DashMap::compare_and_swap), but returns the replaced value on success.
     ///
-    /// # Errors
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
     ///
-    /// If the capacity overflows, or the allocator reports a failure, then an error is returned.
-    // TODO: return std::collections::TryReserveError once std::collections::TryReserveErrorKind stabilises.
-    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
-        for shard in self.shards.iter() {
-            shard
-                .write()
-                .try_reserve(additional, |(k, _v)| {
-                    let mut hasher = self.hasher.build_hasher();
-                    k.hash(&mut hasher);
-                    hasher.finish()
-                })
-                .map_err(|_| TryReserveError {})?;
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let versions = DashMap::new();
+    /// versions.insert("config", 1);
+    ///
+    /// assert_eq!(versions.compare_exchange("config", &1, 2), Ok(1));
+    /// assert_eq!(versions.compare_exchange("config", &1, 3), Err(Some(2)));
+    /// ```
+    pub fn compare_exchange<Q>(&self, key: &Q, current: &V, new: V) -> Result<V, Option<V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+        V: PartialEq + Clone,
+    {
+        self._compare_exchange(key, current, new)
+    }
+
+    /// Replaces the value for `key` with the result of `f`, returning the previous value.
+    ///
+    /// `f` is given the current value and returns `Some` with the new value, or `None` to leave it
+    /// unchanged, in which case a clone of the current value is returned as the error. If the key
+    /// is not present, `f` is not called and `Err(None)` is returned. Everything happens under a
+    /// single shard lock.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let counters = DashMap::new();
+    /// counters.insert("retries", 2);
+    ///
+    /// let bump = |n: &i32| (*n < 3).then(|| n + 1);
+    /// assert_eq!(counters.fetch_update("retries", bump), Ok(2));
+    /// assert_eq!(counters.fetch_update("retries", bump), Err(Some(3)));
+    /// assert_eq!(counters.fetch_update("missing", bump), Err(None));
+    /// ```
+    pub fn fetch_update<Q>(&self, key: &Q, f: impl FnOnce(&V) -> Option<V>) -> Result<V, Option<V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+        V: Clone,
+    {
+        self._fetch_update(key, f)
+    }
+
+    /// Like [`fetch_update`](DashMap::fetch_update), but inserts the result of `insert` if the key is not present.
+    ///
+    /// Returns `Ok(None)` if the value was inserted, `Ok(Some(previous))` if it was replaced, and
+    /// `Err(current)` if `f` returned `None`.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let versions = DashMap::new();
+    /// let next = |v: &u32| Some(v + 1);
+    ///
+    /// assert_eq!(versions.fetch_update_or_insert("config", next, || 1), Ok(None));
+    /// assert_eq!(versions.fetch_update_or_insert("config", next, || 1), Ok(Some(1)));
+    /// assert_eq!(*versions.get("config").unwrap(), 2);
+    /// ```
+    pub fn fetch_update_or_insert(
+        &self,
+        key: K,
+        f: impl FnOnce(&V) -> Option<V>,
+        insert: impl FnOnce() -> V,
+    ) -> Result<Option<V>, V>
+    where
+        V: Clone,
+    {
+        self._fetch_update_or_insert(key, f, insert)
+    }
+
+    /// Swaps the values of two keys. Returns false and changes nothing if either key is not present.
+    ///
+    /// Both shards involved are write-locked for the duration of the swap, lower index first,
+    /// so concurrent swaps never observe or leave behind a half-finished exchange.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let seats = DashMap::new();
+    /// seats.insert("alice", 1);
+    /// seats.insert("bob", 2);
+    ///
+    /// assert!(seats.swap("alice", "bob"));
+    /// assert_eq!(*seats.get("alice").unwrap(), 2);
+    /// assert_eq!(*seats.get("bob").unwrap(), 1);
+    /// assert!(!seats.swap("alice", "carol"));
+    /// ```
+    pub fn swap<Q>(&self, a: &Q, b: &Q) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._swap(a, b)
+    }
+
+    /// Moves the value stored under `from` to the key `to`, overwriting any value `to` already had.
+    /// Returns false and changes nothing if `from` is not present.
+    ///
+    /// Both shards involved are write-locked until the move is complete, lower index first,
+    /// so no other thread can observe the value missing from both keys or present under both.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let cache = DashMap::new();
+    /// cache.insert("draft-1", "contents");
+    ///
+    /// assert!(cache.move_key("draft-1", "post-1"));
+    /// assert!(!cache.contains_key("draft-1"));
+    /// assert_eq!(*cache.get("post-1").unwrap(), "contents");
+    /// assert!(!cache.move_key("draft-1", "post-2"));
+    /// ```
+    pub fn move_key<Q>(&self, from: &Q, to: K) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._move_key(from, to)
+    }
+
+    /// Remove excess capacity to reduce memory usage.
+    ///
+    /// Every shard is write-locked in turn while its table is rehashed, so this should not be called on a hot path.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use dashmap::try_result::TryResult;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", 21);
+    /// assert!(map.capacity() > 0);
+    /// map.remove("Johnny");
+    /// map.shrink_to_fit();
+    /// assert_eq!(map.capacity(), 0);
+    /// ```
+    pub fn shrink_to_fit(&self) {
+        self._shrink_to_fit();
+    }
+
+    /// Retain elements that whose predicates return true
+    /// and discard elements whose predicates return false.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let people = DashMap::new();
+    /// people.insert("Albin", 15);
+    /// people.insert("Jones", 22);
+    /// people.insert("Charlie", 27);
+    /// people.retain(|_, v| *v > 20);
+    /// assert_eq!(people.len(), 2);
+    /// ```
+    pub fn retain(&self, f: impl FnMut(&K, &mut V) -> bool) {
+        self._retain(f);
+    }
+
+    /// Like [`retain`](DashMap::retain), but returns the removed entries.
+    ///
+    /// Each shard is write-locked once, exactly like `retain` does.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let people = DashMap::new();
+    /// people.insert("Albin", 15);
+    /// people.insert("Jones", 22);
+    ///
+    /// let removed = people.retain_collect(|_, age| *age > 20);
+    /// assert_eq!(removed, [("Albin", 15)]);
+    /// ```
+    pub fn retain_collect(&self, f: impl FnMut(&K, &mut V) -> bool) -> Vec<(K, V)> {
+        self._retain_collect(f)
+    }
+
+    /// Like [`retain`](DashMap::retain), but returns how many entries were removed.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let people = DashMap::new();
+    /// people.insert("Albin", 15);
+    /// people.insert("Jones", 22);
+    /// assert_eq!(people.retain_count(|_, age| *age > 20), 1);
+    /// ```
+    pub fn retain_count(&self, f: impl FnMut(&K, &mut V) -> bool) -> usize {
+        self._retain_count(f)
+    }
+
+    /// Like [`retain`](DashMap::retain), but also shrinks the shards that end up mostly empty.
+    ///
+    /// A shard is shrunk to fit its remaining entries while it is still locked for retaining,
+    /// if its capacity is more than four times the number of entries left in it.
+    /// This saves a second pass over the shards compared to calling
+    /// [`shrink_to_fit`](DashMap::shrink_to_fit) afterwards.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// for i in 0..10_000 {
+    ///     map.insert(i, i);
+    /// }
+    /// let capacity = map.capacity();
+    ///
+    /// map.retain_and_shrink(|k, _| *k < 10);
+    /// assert_eq!(map.len(), 10);
+    /// assert!(map.capacity() < capacity);
+    /// ```
+    pub fn retain_and_shrink(&self, f: impl FnMut(&K, &mut V) -> bool) {
+        self._retain_and_shrink(f);
+    }
+
+    /// Moves the entries for which `f` returns true into a new map with the same hasher
+    /// and number of shards, and returns it.
+    ///
+    /// The shards are processed one at a time. Keys and values are moved, not cloned.
+    /// While a shard's matching entries are being moved, they are in neither map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let hot = DashMap::new();
+    /// hot.insert("a", 1);
+    /// hot.insert("b", 50);
+    ///
+    /// let cold = hot.split_off(|_, hits| *hits < 10);
+    /// assert_eq!(*cold.get("a").unwrap(), 1);
+    /// assert_eq!(hot.len(), 1);
+    /// ```
+    pub fn split_off(&self, f: impl FnMut(&K, &V) -> bool) -> DashMap<K, V, S> {
+        let target = DashMap::with_hasher_and_shard_amount(self.hasher.clone(), self.shards.len());
+        self._split_off_into(f, &target);
+        target
+    }
+
+    /// Like [`split_off`](DashMap::split_off), but moves the entries into an existing map,
+    /// overwriting the values of keys it already contains.
+    ///
+    /// Each shard of this map is unlocked before its entries are inserted into `target`.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into
+    /// either map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let hot = DashMap::new();
+    /// hot.insert("a", 1);
+    /// let cold = DashMap::new();
+    /// cold.insert("z", 0);
+    ///
+    /// hot.split_off_into(|_, hits| *hits < 10, &cold);
+    /// assert!(hot.is_empty());
+    /// assert_eq!(cold.len(), 2);
+    /// ```
+    pub fn split_off_into<T: BuildHasher + Clone>(
+        &self,
+        f: impl FnMut(&K, &V) -> bool,
+        target: &DashMap<K, V, T>,
+    ) {
+        self._split_off_into(f, target);
+    }
+
+    /// Folds every entry of the map into an accumulator, read-locking each shard once.
+    ///
+    /// This is not atomic across shards: entries may be inserted into or removed from shards
+    /// that have not been visited yet, or that have already been folded.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stock = DashMap::new();
+    /// stock.insert("apples", 3);
+    /// stock.insert("pears", 5);
+    ///
+    /// let largest = stock.fold(0, |largest, _, v| largest.max(*v));
+    /// assert_eq!(largest, 5);
+    /// ```
+    pub fn fold<A>(&self, init: A, f: impl FnMut(A, &K, &V) -> A) -> A {
+        self._fold(init, f)
+    }
+
+    /// Sums the values of the map, read-locking each shard once.
+    ///
+    /// Like [`fold`](DashMap::fold), this is not atomic across shards.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stock = DashMap::new();
+    /// stock.insert("apples", 3);
+    /// stock.insert("pears", 5);
+    /// assert_eq!(stock.sum_values::<i32>(), 8);
+    /// ```
+    pub fn sum_values<T>(&self) -> T
+    where
+        T: for<'v> core::iter::Sum<&'v V> + core::iter::Sum<T>,
+    {
+        self._sum_values()
+    }
+
+    /// Removes the entries for which `f` returns true, and yields them as owned key-value pairs.
+    ///
+    /// Shards are processed lazily, one at a time: when the iterator reaches a shard, the shard is
+    /// write-locked only while its matching entries are moved out. If the iterator is dropped early,
+    /// shards it has not reached yet are left untouched.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let cache = DashMap::new();
+    /// cache.insert("fresh", 1);
+    /// cache.insert("stale", 90);
+    ///
+    /// let expired: Vec<_> = cache.extract_if(|_, age| *age > 60).collect();
+    /// assert_eq!(expired, [("stale", 90)]);
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    pub fn extract_if<F>(&'a self, f: F) -> impl Iterator<Item = (K, V)> + 'a
+    where
+        F: FnMut(&K, &mut V) -> bool + 'a,
+    {
+        self._extract_if(f)
+    }
+
+    /// Fetches the total number of key-value pairs stored in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let people = DashMap::new();
+    /// people.insert("Albin", 15);
+    /// people.insert("Jones", 22);
+    /// people.insert("Charlie", 27);
+    /// assert_eq!(people.len(), 3);
+    /// ```
+    pub fn len(&self) -> usize {
+        self._len()
+    }
+
+    /// Checks if the map is empty or not.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::<(), ()>::new();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self._is_empty()
+    }
+
+    /// Removes all key-value pairs in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stats = DashMap::new();
+    /// stats.insert("Goals", 4);
+    /// assert!(!stats.is_empty());
+    /// stats.clear();
+    /// assert!(stats.is_empty());
+    /// ```
+    pub fn clear(&self) {
+        self._clear();
+    }
+
+    /// Moves all key-value pairs into a new map with the same hasher and number of shards,
+    /// and returns it, leaving this map empty.
+    ///
+    /// Each shard is only locked for as long as it takes to swap its table for an empty one,
+    /// so writers are barely stalled. The result is atomic per shard but not across shards:
+    /// entries inserted into a shard after it has been swapped stay in this map, even if other
+    /// shards have not been swapped yet.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let pending = DashMap::new();
+    /// pending.insert("a", 1);
+    ///
+    /// let flushed = pending.take_all();
+    /// assert!(pending.is_empty());
+    /// assert_eq!(*flushed.get("a").unwrap(), 1);
+    /// ```
+    pub fn take_all(&self) -> DashMap<K, V, S> {
+        let shards = self
+            .shards
+            .iter()
+            .map(|shard| CachePadded::new(RwLock::new(core::mem::take(&mut *shard.write()))))
+            .collect();
+
+        DashMap {
+            shift: self.shift,
+            shards,
+            hasher: self.hasher.clone(),
+            loads: Default::default(),
+        }
+    }
+
+    /// Removes all key-value pairs from the map, and calls `f` with each of them.
+    ///
+    /// The shards are emptied one at a time. Each shard is unlocked before `f` is called
+    /// for its entries, so `f` can run for a long time, or even access the map, without blocking it.
+    /// If `f` panics, the rest of the entries of the shard being processed are dropped,
+    /// and the shards that have not been reached yet are left untouched.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let handles = DashMap::new();
+    /// handles.insert(1, "file-1");
+    /// handles.insert(2, "file-2");
+    ///
+    /// let mut closed = Vec::new();
+    /// handles.clear_with(|_, handle| closed.push(handle));
+    /// closed.sort();
+    /// assert_eq!(closed, ["file-1", "file-2"]);
+    /// assert!(handles.is_empty());
+    /// ```
+    pub fn clear_with(&self, mut f: impl FnMut(K, V)) {
+        for shard in self.shards.iter() {
+            let entries = core::mem::take(&mut *shard.write());
+            for (k, v) in entries {
+                f(k, v);
+            }
+        }
+    }
+
+    /// Removes all key-value pairs from the map, and yields them as owned pairs.
+    ///
+    /// The shards are emptied one at a time as the iterator reaches them, so inserts into
+    /// other shards are not blocked while the entries are processed. This also means that
+    /// entries inserted into a shard that has already been emptied stay in the map.
+    /// Like [`HashMap::drain`](std::collections::HashMap::drain), the map is emptied even if the
+    /// iterator is dropped early: the shards it has not reached yet are cleared when it is dropped.
+    /// See [`drain_keep_rest`](DashMap::drain_keep_rest) to keep them instead.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let pending = DashMap::new();
+    /// pending.insert("a", 1);
+    /// pending.insert("b", 2);
+    ///
+    /// let mut flushed: Vec<_> = pending.drain().collect();
+    /// flushed.sort();
+    /// assert_eq!(flushed, [("a", 1), ("b", 2)]);
+    /// assert!(pending.is_empty());
+    /// ```
+    pub fn drain(&'a self) -> Drain<'a, K, V> {
+        Drain::new(self, true)
+    }
+
+    /// Like [`drain`](DashMap::drain), but only the shards the iterator reaches are emptied:
+    /// if it is dropped early, the shards it has not reached yet keep their entries.
+    ///
+    /// The shard being drained when the iterator is dropped has already been emptied, and its
+    /// remaining entries are dropped with the iterator.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let pending = DashMap::with_shard_amount(4);
+    /// for i in 0..100 {
+    ///     pending.insert(i, i);
+    /// }
+    ///
+    /// // Take a few entries, leaving the rest for later.
+    /// let batch: Vec<_> = pending.drain_keep_rest().take(3).collect();
+    /// assert_eq!(batch.len(), 3);
+    /// assert!(!pending.is_empty());
+    /// ```
+    pub fn drain_keep_rest(&'a self) -> Drain<'a, K, V> {
+        Drain::new(self, false)
+    }
+
+    /// Removes all key-value pairs stored in the shard with the given index.
+    ///
+    /// Shard indices go from 0 up to [`shard_count`](DashMap::shard_count), and each key always maps to the same shard.
+    /// This lets workloads that partition their keys by shard invalidate a whole partition at once.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_index` is out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::with_shard_amount(4);
+    /// for i in 0..100 {
+    ///     map.insert(i, i);
+    /// }
+    /// for shard in 0..map.shard_count() {
+    ///     map.clear_shard(shard);
+    /// }
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn clear_shard(&self, shard_index: usize) {
+        self._clear_shard(shard_index);
+    }
+
+    /// Returns the number of key-value pairs stored in the shard with the given index.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_index` is out of range.
+    pub fn shard_len(&self, shard_index: usize) -> usize {
+        self._shard_len(shard_index)
+    }
+
+    /// Returns how many key-value pairs the map can store without reallocating.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn capacity(&self) -> usize {
+        self._capacity()
+    }
+
+    /// Returns the number of key-value pairs in each shard, in shard order.
+    /// The returned vector has [`shard_count`](DashMap::shard_count) elements.
+    ///
+    /// Useful to spot a hasher that spreads the keys unevenly. Each shard is only locked
+    /// while its length is read, so the result is not a consistent snapshot under concurrent writes.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// assert_eq!(map.shard_lengths().iter().sum::<usize>(), 2);
+    /// ```
+    pub fn shard_lengths(&self) -> Vec<usize> {
+        self._shard_lengths()
+    }
+
+    /// Modify a specific value according to a function.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stats = DashMap::new();
+    /// stats.insert("Goals", 4);
+    /// stats.alter("Goals", |_, v| v * 2);
+    /// assert_eq!(*stats.get("Goals").unwrap(), 8);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `alter` will abort the process
+    pub fn alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V)
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._alter(key, f);
+    }
+
+    /// Modify every value in the map according to a function.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stats = DashMap::new();
+    /// stats.insert("Wins", 4);
+    /// stats.insert("Losses", 2);
+    /// stats.alter_all(|_, v| v + 1);
+    /// assert_eq!(*stats.get("Wins").unwrap(), 5);
+    /// assert_eq!(*stats.get("Losses").unwrap(), 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `alter_all` will abort the process
+    pub fn alter_all(&self, f: impl FnMut(&K, V) -> V) {
+        self._alter_all(f);
+    }
+
+    /// Inserts, updates or removes an entry according to a function, all under a single lock.
+    ///
+    /// The function receives the current key and value if the key is present, and `None` otherwise.
+    /// If it returns `Some`, that value is stored in the map. If it returns `None`, the entry is removed,
+    /// or stays absent. Returns a reference to the value now stored in the map, if any.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stock = DashMap::new();
+    /// let restock = |entry: Option<(&&str, &mut u32)>| Some(entry.map_or(10, |(_, n)| *n + 10));
+    /// assert_eq!(*stock.compute("apples", restock).unwrap(), 10);
+    /// assert_eq!(*stock.compute("apples", restock).unwrap(), 20);
+    /// assert!(stock.compute("apples", |_| None).is_none());
+    /// assert!(!stock.contains_key("apples"));
+    /// ```
+    pub fn compute(
+        &'a self,
+        key: K,
+        f: impl FnOnce(Option<(&K, &mut V)>) -> Option<V>,
+    ) -> Option<RefMut<'a, K, V>> {
+        self._compute(key, f)
+    }
+
+    /// Scoped access into an item of the map according to a function.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let warehouse = DashMap::new();
+    /// warehouse.insert(4267, ("Banana", 100));
+    /// warehouse.insert(2359, ("Pear", 120));
+    /// let fruit = warehouse.view(&4267, |_k, v| *v);
+    /// assert_eq!(fruit, Some(("Banana", 100)));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `view` will abort the process
+    pub fn view<Q, R>(&self, key: &Q, f: impl FnOnce(&K, &V) -> R) -> Option<R>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._view(key, f)
+    }
+
+    /// Checks if the map contains a specific key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let team_sizes = DashMap::new();
+    /// team_sizes.insert("Dakota Cherries", 23);
+    /// assert!(team_sizes.contains_key("Dakota Cherries"));
+    /// ```
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._contains_key(key)
+    }
+
+    /// Checks if the map contains every one of `keys`. Returns true if `keys` is empty.
+    ///
+    /// The keys are grouped by shard, so each shard is read-locked at most once, and shards
+    /// after the first missing key are not locked at all.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// assert!(map.contains_all(["a", "b"].iter()));
+    /// assert!(!map.contains_all(["a", "c"].iter()));
+    /// ```
+    pub fn contains_all<'k, Q, I>(&self, keys: I) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized + 'k,
+        I: IntoIterator<Item = &'k Q>,
+    {
+        !self._contains_matching(keys, false)
+    }
+
+    /// Checks if the map contains at least one of `keys`. Returns false if `keys` is empty.
+    ///
+    /// The keys are grouped by shard, so each shard is read-locked at most once, and shards
+    /// after the first present key are not locked at all.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    /// assert!(map.contains_any(["c", "a"].iter()));
+    /// assert!(!map.contains_any(["c", "d"].iter()));
+    /// ```
+    pub fn contains_any<'k, Q, I>(&self, keys: I) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized + 'k,
+        I: IntoIterator<Item = &'k Q>,
+    {
+        self._contains_matching(keys, true)
+    }
+
+    /// Advanced entry API that tries to mimic `std::collections::HashMap`.
+    /// See the documentation on `dashmap::mapref::entry` for more details.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn entry(&'a self, key: K) -> Entry<'a, K, V> {
+        self._entry(key)
+    }
+
+    /// Like [`entry`](DashMap::entry), but for a map shared through an [`Arc`].
+    /// The returned [`OwnedEntry`] and the guards it produces keep the map alive.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::sync::Arc;
+    ///
+    /// let sessions = Arc::new(DashMap::new());
+    /// let mut session = sessions.entry_owned(7).or_insert_with(Vec::new);
+    /// std::thread::spawn(move || session.push("login")).join().unwrap();
+    /// assert_eq!(*sessions.get(&7).unwrap(), ["login"]);
+    /// ```
+    pub fn entry_owned(self: &Arc<Self>, key: K) -> OwnedEntry<K, V, S> {
+        self._entry_owned(key)
+    }
+
+    /// Advanced entry API that tries to mimic `std::collections::HashMap`.
+    /// See the documentation on `dashmap::mapref::entry` for more details.
+    ///
+    /// Returns None if the shard is currently locked. The key is dropped in that case,
+    /// use [`try_entry_ref`](DashMap::try_entry_ref) to keep it when it is expensive to rebuild.
+    ///
+    /// **Locking behaviour:** Never blocks, even when holding a reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let counters = DashMap::new();
+    /// *counters.try_entry("hits").unwrap().or_insert(0) += 1;
+    ///
+    /// let hits = counters.get("hits").unwrap();
+    /// assert!(counters.try_entry("hits").is_none());
+    /// drop(hits);
+    ///
+    /// assert!(counters.try_entry("hits").is_some());
+    /// ```
+    pub fn try_entry(&'a self, key: K) -> Option<Entry<'a, K, V>> {
+        self._try_entry(key)
+    }
+
+    /// Like [`entry`](DashMap::entry), but gives up and returns None if the shard
+    /// can not be locked within `timeout`.
+    ///
+    /// None means that the shard stayed locked by someone else, not that the key is absent.
+    /// The key is dropped in that case.
+    ///
+    /// **Locking behaviour:** Waits at most `timeout`, even when holding a reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::time::Duration;
+    ///
+    /// let counters = DashMap::new();
+    /// counters.insert("hits", 0);
+    ///
+    /// let hits = counters.get("hits").unwrap();
+    /// assert!(counters.entry_timeout("hits", Duration::from_millis(1)).is_none());
+    /// drop(hits);
+    ///
+    /// *counters.entry_timeout("hits", Duration::from_millis(1)).unwrap().or_insert(0) += 1;
+    /// assert_eq!(*counters.get("hits").unwrap(), 1);
+    /// ```
+    pub fn entry_timeout(&'a self, key: K, timeout: Duration) -> Option<Entry<'a, K, V>> {
+        self._entry_timeout(key, timeout)
+    }
+
+    /// Like [`entry`](DashMap::entry), but instead of blocking the thread while the shard
+    /// is locked by someone else, the returned future yields back to the executor until
+    /// the shard is unlocked.
+    ///
+    /// This works with any executor. The returned entry keeps the shard locked like any other guard,
+    /// so it must not be held across an `.await`, or other tasks and threads may be blocked or deadlock.
+    /// A future dropped while waiting leaves its waker with the shard until the shard is next unlocked.
+    ///
+    /// **Locking behaviour:** Never blocks the thread, but the future never completes if the current
+    /// task holds a reference into the same shard.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// async fn count(hits: &DashMap<&'static str, u32>) {
+    ///     *hits.entry_async("home").await.or_insert(0) += 1;
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn entry_async(&'a self, key: K) -> Entry<'a, K, V> {
+        self._entry_async(key).await
+    }
+
+    /// Like [`get`](DashMap::get), with the hash of `key` computed beforehand by [`hash_key`](DashMap::hash_key).
+    ///
+    /// This avoids hashing the key again when looking it up several times, for example in several maps
+    /// sharing the same hasher. If `hash` is not the hash of `key`, the entry may not be found.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn get_hashed<Q>(&'a self, hash: u64, key: &Q) -> Option<Ref<'a, K, V>>
+    where
+        Q: Equivalent<K> + ?Sized,
+    {
+        self._get_hashed(hash, key)
+    }
+
+    /// Like [`insert`](DashMap::insert), with the hash of `key` computed beforehand by [`hash_key`](DashMap::hash_key).
+    ///
+    /// If `hash` is not the hash of `key`, later lookups of `key` may not find the entry.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn insert_hashed(&self, hash: u64, key: K, value: V) -> Option<V> {
+        self._insert_hashed(hash, key, value)
+    }
+
+    /// Like [`remove`](DashMap::remove), with the hash of `key` computed beforehand by [`hash_key`](DashMap::hash_key).
+    ///
+    /// If `hash` is not the hash of `key`, the entry may not be found.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn remove_hashed<Q>(&self, hash: u64, key: &Q) -> Option<(K, V)>
+    where
+        Q: Equivalent<K> + ?Sized,
+    {
+        self._remove_hashed(hash, key)
+    }
+
+    /// Like [`entry`](DashMap::entry), with the hash of `key` computed beforehand by [`hash_key`](DashMap::hash_key).
+    ///
+    /// If `hash` is not the hash of `key`, an existing entry for `key` may not be found.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn entry_hashed(&'a self, hash: u64, key: K) -> Entry<'a, K, V> {
+        self._entry_hashed(hash, key)
+    }
+
+    /// Advanced entry API that tries to mimic `hashbrown::HashMap::entry_ref`.
+    /// See the documentation on `dashmap::mapref::entry_ref` for more details.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn entry_ref<'q, Q>(&'a self, key: &'q Q) -> EntryRef<'a, 'q, K, Q, V>
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        self._entry_ref(key)
+    }
+
+    /// Like [`entry_ref`](DashMap::entry_ref), but returns None if the shard is currently locked.
+    /// The key is only borrowed, so nothing is lost when the shard is locked.
+    ///
+    /// **Locking behaviour:** Never blocks, even when holding a reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let counters: DashMap<String, u32> = DashMap::new();
+    /// let key = String::from("hits");
+    ///
+    /// let hits = counters.entry(key.clone()).or_insert(0).downgrade();
+    /// assert!(counters.try_entry_ref(&key).is_none());
+    /// drop(hits);
+    ///
+    /// *counters.try_entry_ref(&key).unwrap().or_insert(0) += 1;
+    /// assert_eq!(*counters.get("hits").unwrap(), 1);
+    /// ```
+    pub fn try_entry_ref<'q, Q>(&'a self, key: &'q Q) -> Option<EntryRef<'a, 'q, K, Q, V>>
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        self._try_entry_ref(key)
+    }
+
+    /// Reserves capacity for at least `additional` more key-value pairs, spread evenly over the shards.
+    ///
+    /// Each shard reserves `additional / shard_amount`, rounded up, so the map as a whole can take
+    /// at least `additional` more pairs if the keys are spread evenly. Shards are write-locked one at a time.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity of a shard overflows `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map: DashMap<u32, u32> = DashMap::new();
+    /// map.reserve(1000);
+    /// assert!(map.capacity() >= 1000);
+    /// ```
+    pub fn reserve(&self, additional: usize) {
+        self._reserve(additional);
+    }
+
+    /// Advanced entry API that tries to mimic `std::collections::HashMap::try_reserve`.
+    /// Tries to reserve capacity for at least `shard * additional`
+    /// and may reserve more space to avoid frequent reallocations.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an error is returned.
+    // TODO: return std::collections::TryReserveError once std::collections::TryReserveErrorKind stabilises.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        for shard in self.shards.iter() {
+            shard
+                .write()
+                .try_reserve(additional, |(k, _v)| {
+                    let mut hasher = self.hasher.build_hasher();
+                    k.hash(&mut hasher);
+                    hasher.finish()
+                })
+                .map_err(|_| TryReserveError {})?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: 'a + BuildHasher + Clone> DashMap<K, V, S> {
+    fn _insert(&self, key: K, value: V) -> Option<V> {
+        self._insert_hashed(self.hash_u64(&key), key, value)
+    }
+
+    fn _insert_and_get(&'a self, key: K, value: V) -> (Option<V>, RefMut<'a, K, V>) {
+        match self._entry(key) {
+            Entry::Occupied(mut entry) => {
+                let old = entry.insert(value);
+                (Some(old), entry.into_ref())
+            }
+            Entry::Vacant(entry) => (None, entry.insert(value)),
+        }
+    }
+
+    fn _insert_and_get_ref(&'a self, key: K, value: V) -> (Option<V>, Ref<'a, K, V>) {
+        let (old, r) = self._insert_and_get(key, value);
+        (old, r.downgrade())
+    }
+
+    fn _insert_hashed(&self, hash: u64, key: K, value: V) -> Option<V> {
+        match self._entry_hashed(hash, key) {
+            Entry::Occupied(mut o) => Some(o.insert(value)),
+            Entry::Vacant(v) => {
+                v.insert(value);
+                None
+            }
+        }
+    }
+
+    fn _entry_many<I: IntoIterator<Item = K>>(
+        &self,
+        keys: I,
+        mut f: impl FnMut(BatchEntry<'_, K, V>),
+    ) {
+        self.for_each_batched(keys.into_iter().map(|key| (key, ())), |key, (), entry| {
+            f(BatchEntry::new(key, entry))
+        });
+    }
+
+    /// Groups `items` by the shard of their key, then write-locks each shard once, in index order,
+    /// and calls `f` with the entry of every key of its group, in the order they were yielded.
+    fn for_each_batched<T>(
+        &self,
+        items: impl IntoIterator<Item = (K, T)>,
+        mut f: impl FnMut(K, T, hash_table::Entry<'_, (K, V)>),
+    ) {
+        let mut batches: Vec<Vec<(u64, K, T)>> = core::iter::repeat_with(Vec::new)
+            .take(self.shards.len())
+            .collect();
+
+        for (key, item) in items {
+            let hash = self.hash_u64(&key);
+            batches[self.determine_shard(hash as usize)].push((hash, key, item));
+        }
+
+        for (idx, batch) in batches.into_iter().enumerate() {
+            if batch.is_empty() {
+                continue;
+            }
+
+            let mut shard = self.shards[idx].write();
+
+            for (hash, key, item) in batch {
+                let entry = shard.entry(
+                    hash,
+                    |(k, _v)| k == &key,
+                    |(k, _v)| {
+                        let mut hasher = self.hasher.build_hasher();
+                        k.hash(&mut hasher);
+                        hasher.finish()
+                    },
+                );
+                f(key, item, entry);
+            }
+        }
+    }
+
+    fn merge_entries(
+        &self,
+        entries: impl IntoIterator<Item = (K, V)>,
+        resolve: &mut impl FnMut(&K, V, V) -> V,
+    ) {
+        self.for_each_batched(entries, |key, value, entry| match entry {
+            hash_table::Entry::Occupied(entry) => {
+                // Should `resolve` panic, the entry stays removed.
+                let ((k, old), entry) = entry.remove();
+                let merged = resolve(&k, old, value);
+                entry.insert((k, merged));
+            }
+            hash_table::Entry::Vacant(entry) => {
+                entry.insert((key, value));
+            }
+        });
+    }
+
+    fn _insert_many<I: IntoIterator<Item = (K, V)>>(&self, iter: I) -> usize {
+        let mut overwritten = 0;
+
+        self.for_each_batched(iter, |key, value, entry| match entry {
+            hash_table::Entry::Occupied(mut entry) => {
+                entry.get_mut().1 = value;
+                overwritten += 1;
+            }
+            hash_table::Entry::Vacant(entry) => {
+                entry.insert((key, value));
+            }
+        });
+
+        overwritten
+    }
+
+    fn _remove<Q>(&self, key: &Q) -> Option<(K, V)>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._remove_hashed(self.hash_u64(&key), key)
+    }
+
+    fn _remove_hashed<Q>(&self, hash: u64, key: &Q) -> Option<(K, V)>
+    where
+        Q: Equivalent<K> + ?Sized,
+    {
+        let idx = self.determine_shard(hash as usize);
+
+        let mut shard = self.shards[idx].write();
+
+        if let Ok(entry) = shard.find_entry(hash, |(k, _v)| key.equivalent(k)) {
+            let ((k, v), _) = entry.remove();
+            Some((k, v))
+        } else {
+            None
+        }
+    }
+
+    fn _remove_if<Q>(&self, key: &Q, f: impl FnOnce(&K, &V) -> bool) -> Option<(K, V)>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let mut shard = self.shards[idx].write();
+
+        if let Ok(entry) = shard.find_entry(hash, |(k, _v)| key.equivalent(k)) {
+            let (k, v) = entry.get();
+            if f(k, v) {
+                let ((k, v), _) = entry.remove();
+                Some((k, v))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    fn _remove_if_mut<Q>(&self, key: &Q, f: impl FnOnce(&K, &mut V) -> bool) -> Option<(K, V)>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let mut shard = self.shards[idx].write();
+
+        if let Ok(mut entry) = shard.find_entry(hash, |(k, _v)| key.equivalent(k)) {
+            let (k, v) = entry.get_mut();
+            if f(k, v) {
+                let ((k, v), _) = entry.remove();
+                Some((k, v))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    fn _iter(&'a self) -> Iter<'a, K, V> {
+        Iter::new(self)
+    }
+
+    fn _range<R>(&'a self, range: R) -> impl Iterator<Item = RefMulti<'a, K, V>>
+    where
+        K: Ord,
+        R: RangeBounds<K>,
+    {
+        let mut matches = Vec::new();
+
+        for shard in self.shards.iter() {
+            let shard = shard.read();
+            // SAFETY: The data will not outlive the guard, since every `RefMulti` keeps it alive.
+            let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+            let guard = Arc::new(guard);
+
+            matches.extend(
+                shard
+                    .iter()
+                    .filter(|(k, _v)| range.contains(k))
+                    .map(|(k, v)| RefMulti::new(guard.clone(), k, v)),
+            );
+        }
+
+        matches.sort_unstable_by(|a, b| a.key().cmp(b.key()));
+        matches.into_iter()
+    }
+
+    fn _multi_get<'k, Q, I>(&'a self, keys: I) -> impl Iterator<Item = Option<RefMulti<'a, K, V>>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized + 'k,
+        I: IntoIterator<Item = &'k Q>,
+    {
+        let mut lookups: Vec<_> = keys
+            .into_iter()
+            .enumerate()
+            .map(|(pos, key)| {
+                let hash = self.hash_u64(&key);
+                (self.determine_shard(hash as usize), pos, hash, key)
+            })
+            .collect();
+        // Grouped by shard, to lock the shards in order (see `DashMap::shards`).
+        lookups.sort_unstable_by_key(|&(idx, pos, ..)| (idx, pos));
+
+        let mut results: Vec<_> = (0..lookups.len()).map(|_| None).collect();
+
+        let mut rest = &lookups[..];
+        while let Some(&(idx, ..)) = rest.first() {
+            let len = rest.iter().take_while(|lookup| lookup.0 == idx).count();
+            let (group, tail) = rest.split_at(len);
+            rest = tail;
+
+            let shard = self.shards[idx].read();
+            // SAFETY: The data will not outlive the guard, since every `RefMulti` keeps it alive.
+            let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+            let guard = Arc::new(guard);
+
+            for &(_idx, pos, hash, key) in group {
+                results[pos] = shard
+                    .find(hash, |(k, _v)| key.equivalent(k))
+                    .map(|(k, v)| RefMulti::new(guard.clone(), k, v));
+            }
+        }
+
+        results.into_iter()
+    }
+
+    fn _get_many<Q, const N: usize>(&'a self, keys: [&Q; N]) -> [Option<RefMulti<'a, K, V>>; N]
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let mut refs = self._multi_get(keys);
+        core::array::from_fn(|_| refs.next().flatten())
+    }
+
+    fn _get_many_cloned<Q, const N: usize>(&self, keys: [&Q; N]) -> [Option<V>; N]
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+        V: Clone,
+    {
+        self._get_many(keys).map(|r| r.map(|r| r.value().clone()))
+    }
+
+    fn _get_many_mut<Q, const N: usize>(
+        &'a self,
+        keys: [&Q; N],
+    ) -> Option<[RefMutMulti<'a, K, V>; N]>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hashes = keys.map(|key| self.hash_u64(&key));
+        let shard_of = |i: usize| self.determine_shard(hashes[i] as usize);
+
+        let mut order: [usize; N] = core::array::from_fn(|i| i);
+        // Grouped by shard, to lock the shards in order (see `DashMap::shards`).
+        order.sort_unstable_by_key(|&i| shard_of(i));
+
+        let mut entries: Vec<Option<(_, *mut (K, V))>> = (0..N).map(|_| None).collect();
+
+        let mut rest = &order[..];
+        while let Some(&first) = rest.first() {
+            let idx = shard_of(first);
+            let len = rest.iter().take_while(|&&i| shard_of(i) == idx).count();
+            let (group, tail) = rest.split_at(len);
+            rest = tail;
+
+            let shard = self.shards[idx].write();
+            // SAFETY: The data will not outlive the guard, since every `RefMutMulti` keeps it alive.
+            let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+            let guard = Arc::new(guard);
+
+            for &i in group {
+                let entry: *mut (K, V) =
+                    shard.find_mut(hashes[i], |(k, _v)| keys[i].equivalent(k))?;
+                if entries.iter().flatten().any(|(_, other)| *other == entry) {
+                    return None;
+                }
+                entries[i] = Some((guard.clone(), entry));
+            }
+        }
+
+        let refs: Vec<_> = entries
+            .into_iter()
+            .map(|entry| {
+                let (guard, entry) = entry.unwrap();
+                // SAFETY: Every entry was found above and they are all distinct, so these mutable
+                // references do not alias. The shards stay locked as long as the guards are alive.
+                let (k, v) = unsafe { &mut *entry };
+                RefMutMulti::new(guard, k, v)
+            })
+            .collect();
+
+        refs.try_into().ok()
+    }
+
+    fn _iter_mut(&'a self) -> IterMut<'a, K, V> {
+        IterMut::new(self)
+    }
+
+    fn _get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._get_hashed(self.hash_u64(&key), key)
+    }
+
+    fn _get_hashed<Q>(&'a self, hash: u64, key: &Q) -> Option<Ref<'a, K, V>>
+    where
+        Q: Equivalent<K> + ?Sized,
+    {
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].read();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find(hash, |(k, _v)| key.equivalent(k)) {
+            Some(Ref::new(guard, k, v))
+        } else {
+            None
+        }
+    }
+
+    fn _get_owned<Q>(self: &Arc<Self>, key: &Q) -> Option<OwnedRef<K, V, S>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].read();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `OwnedRef`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        let (k, v) = shard.find(hash, |(k, _v)| key.equivalent(k))?;
+        // SAFETY: `guard` read-locks the shard of this map that `k` and `v` were found in.
+        Some(unsafe { OwnedRef::new(self.clone(), guard, k, v) })
+    }
+
+    fn _get_mut<Q>(&'a self, key: &Q) -> Option<RefMut<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].write();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| key.equivalent(k)) {
+            Some(RefMut::new(guard, k, v))
+        } else {
+            None
+        }
+    }
+
+    fn _get_mut_owned<Q>(self: &Arc<Self>, key: &Q) -> Option<OwnedRefMut<K, V, S>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].write();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `OwnedRefMut`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        let (k, v) = shard.find_mut(hash, |(k, _v)| key.equivalent(k))?;
+        // SAFETY: `guard` write-locks the shard of this map that `k` and `v` were found in.
+        Some(unsafe { OwnedRefMut::new(self.clone(), guard, k, v) })
+    }
+
+    fn _try_get<Q>(&'a self, key: &Q) -> TryResult<Ref<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_read() {
+            Some(shard) => shard,
+            None => return TryResult::Locked,
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find(hash, |(k, _v)| key.equivalent(k)) {
+            TryResult::Present(Ref::new(guard, k, v))
+        } else {
+            TryResult::Absent
+        }
+    }
+
+    fn _try_get_mut<Q>(&'a self, key: &Q) -> TryResult<RefMut<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_write() {
+            Some(shard) => shard,
+            None => return TryResult::Locked,
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| key.equivalent(k)) {
+            TryResult::Present(RefMut::new(guard, k, v))
+        } else {
+            TryResult::Absent
+        }
+    }
+
+    fn _get_timeout<Q>(&'a self, key: &Q, timeout: Duration) -> TryResult<Ref<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_read_for(timeout) {
+            Some(shard) => shard,
+            None => return TryResult::Locked,
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find(hash, |(k, _v)| key.equivalent(k)) {
+            TryResult::Present(Ref::new(guard, k, v))
+        } else {
+            TryResult::Absent
+        }
+    }
+
+    fn _get_mut_timeout<Q>(&'a self, key: &Q, timeout: Duration) -> TryResult<RefMut<'a, K, V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_write_for(timeout) {
+            Some(shard) => shard,
+            None => return TryResult::Locked,
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| key.equivalent(k)) {
+            TryResult::Present(RefMut::new(guard, k, v))
+        } else {
+            TryResult::Absent
+        }
+    }
+
+    fn _get_or_insert(&'a self, key: K, default: V) -> Ref<'a, K, V> {
+        self._get_or_insert_with(key, || default)
+    }
+
+    fn _get_or_insert_with(&'a self, key: K, f: impl FnOnce() -> V) -> Ref<'a, K, V> {
+        let hash = self.hash_u64(&key);
+
+        if let Some(r) = self._get_hashed(hash, &key) {
+            return r;
+        }
+
+        self._entry_hashed(hash, key).or_insert_with(f).downgrade()
+    }
+
+    fn _try_get_with<E>(
+        &'a self,
+        mut key: K,
+        init: impl FnOnce() -> Result<V, E>,
+    ) -> Result<Ref<'a, K, V>, Arc<E>>
+    where
+        E: Send + Sync + 'static,
+    {
+        let hash = self.hash_u64(&key);
+        let mut init = Some(init);
+
+        loop {
+            if let Some(r) = self._get_hashed(hash, &key) {
+                return Ok(r);
+            }
+
+            match self.loads.join(hash, key) {
+                loading::Role::Leader(load) => {
+                    // A load may have finished between the lookup above and joining.
+                    if let Some(r) = load.with_key(|key| self._get_hashed(hash, key)) {
+                        return Ok(r);
+                    }
+
+                    // A caller only ever leads one load, since it returns right after.
+                    let init = init.take().unwrap();
+                    return match init() {
+                        Ok(value) => Ok(load.finish(|key| {
+                            self._entry_hashed(hash, key).or_insert(value).downgrade()
+                        })),
+                        Err(err) => {
+                            let err = Arc::new(err);
+                            load.fail(err.clone());
+                            Err(err)
+                        }
+                    };
+                }
+                loading::Role::Waiter(load, k) => {
+                    key = k;
+                    // On success, or if the leader panicked, look the key up again.
+                    if let Some(err) = load.wait() {
+                        if let Ok(err) = err.downcast::<E>() {
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn _get_mut_or_insert(&'a self, key: K, default: V) -> RefMut<'a, K, V> {
+        self._entry(key).or_insert(default)
+    }
+
+    fn _get_mut_or_insert_with(&'a self, key: K, f: impl FnOnce() -> V) -> RefMut<'a, K, V> {
+        self._entry(key).or_insert_with(f)
+    }
+
+    fn _upsert(&self, key: K, value: V) -> Upsert<V> {
+        match self._entry(key) {
+            Entry::Occupied(mut entry) => Upsert::Updated {
+                old: entry.insert(value),
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                Upsert::Inserted
+            }
+        }
+    }
+
+    fn _upsert_with(
+        &self,
+        key: K,
+        insert: impl FnOnce() -> V,
+        update: impl FnOnce(&mut V),
+    ) -> bool {
+        match self._entry(key) {
+            Entry::Occupied(mut entry) => {
+                update(entry.get_mut());
+                true
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(insert());
+                false
+            }
+        }
+    }
+
+    fn _modify_or_insert(
+        &self,
+        key: K,
+        modify: impl FnOnce(&K, &mut V),
+        insert: impl FnOnce(&K) -> V,
+    ) -> bool {
+        match self._entry(key) {
+            Entry::Occupied(entry) => {
+                let mut r = entry.into_ref();
+                let (k, v) = r.pair_mut();
+                modify(k, v);
+                true
+            }
+            Entry::Vacant(entry) => {
+                let value = insert(entry.key());
+                entry.insert(value);
+                false
+            }
+        }
+    }
+
+    fn _compare_exchange<Q>(&self, key: &Q, current: &V, new: V) -> Result<V, Option<V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+        V: PartialEq + Clone,
+    {
+        let Some(mut r) = self._get_mut(key) else {
+            return Err(None);
+        };
+
+        if *r.value() == *current {
+            Ok(core::mem::replace(r.value_mut(), new))
+        } else {
+            Err(Some(r.value().clone()))
+        }
+    }
+
+    fn _fetch_update<Q>(&self, key: &Q, f: impl FnOnce(&V) -> Option<V>) -> Result<V, Option<V>>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+        V: Clone,
+    {
+        let Some(mut r) = self._get_mut(key) else {
+            return Err(None);
+        };
+
+        match f(r.value()) {
+            Some(new) => Ok(core::mem::replace(r.value_mut(), new)),
+            None => Err(Some(r.value().clone())),
+        }
+    }
+
+    fn _fetch_update_or_insert(
+        &self,
+        key: K,
+        f: impl FnOnce(&V) -> Option<V>,
+        insert: impl FnOnce() -> V,
+    ) -> Result<Option<V>, V>
+    where
+        V: Clone,
+    {
+        match self._entry(key) {
+            Entry::Occupied(mut entry) => match f(entry.get()) {
+                Some(new) => Ok(Some(entry.insert(new))),
+                None => Err(entry.get().clone()),
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(insert());
+                Ok(None)
+            }
+        }
+    }
+
+    fn _swap<Q>(&self, a: &Q, b: &Q) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash_a = self.hash_u64(&a);
+        let hash_b = self.hash_u64(&b);
+        let idx_a = self.determine_shard(hash_a as usize);
+        let idx_b = self.determine_shard(hash_b as usize);
+
+        if idx_a == idx_b {
+            let mut shard = self.shards[idx_a].write();
+
+            let entry_a = shard.find(hash_a, |(k, _v)| a.equivalent(k));
+            let entry_b = shard.find(hash_b, |(k, _v)| b.equivalent(k));
+            match (entry_a, entry_b) {
+                (Some(entry_a), Some(entry_b)) if core::ptr::eq(entry_a, entry_b) => return true,
+                (Some(_), Some(_)) => {}
+                _ => return false,
+            }
+
+            let [Some((_, value_a)), Some((_, value_b))] =
+                shard.get_many_mut([hash_a, hash_b], |i, (k, _v)| {
+                    if i == 0 {
+                        a.equivalent(k)
+                    } else {
+                        b.equivalent(k)
+                    }
+                })
+            else {
+                unreachable!()
+            };
+            core::mem::swap(value_a, value_b);
+            return true;
+        }
+
+        let (mut shard_a, mut shard_b) = self.write_two(idx_a, idx_b);
+
+        let Some((_, value_a)) = shard_a.find_mut(hash_a, |(k, _v)| a.equivalent(k)) else {
+            return false;
+        };
+        let Some((_, value_b)) = shard_b.find_mut(hash_b, |(k, _v)| b.equivalent(k)) else {
+            return false;
+        };
+        core::mem::swap(value_a, value_b);
+        true
+    }
+
+    fn _move_key<Q>(&'a self, from: &Q, to: K) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash_from = self.hash_u64(&from);
+        let hash_to = self.hash_u64(&to);
+        let idx_from = self.determine_shard(hash_from as usize);
+        let idx_to = self.determine_shard(hash_to as usize);
+
+        let (mut shard_from, shard_to) = if idx_from == idx_to {
+            (self.shards[idx_from].write(), None)
+        } else {
+            let (shard_from, shard_to) = self.write_two(idx_from, idx_to);
+            (shard_from, Some(shard_to))
+        };
+
+        let Ok(entry) = shard_from.find_entry(hash_from, |(k, _v)| from.equivalent(k)) else {
+            return false;
+        };
+        let ((_, value), _) = entry.remove();
+
+        match shard_to {
+            Some(shard_to) => self.entry_in_shard(hash_to, to, shard_to).insert(value),
+            None => self.entry_in_shard(hash_to, to, shard_from).insert(value),
+        };
+        true
+    }
+
+    fn _replace_existing<Q>(&self, key: &Q, value: V) -> Result<V, V>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        match self._get_mut(key) {
+            Some(mut r) => Ok(core::mem::replace(r.value_mut(), value)),
+            None => Err(value),
+        }
+    }
+
+    fn _insert_if_absent(&self, key: K, value: V) -> Option<V> {
+        match self._entry(key) {
+            Entry::Occupied(_) => Some(value),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    fn _try_insert(
+        &'a self,
+        key: K,
+        value: V,
+    ) -> Result<RefMut<'a, K, V>, OccupiedError<'a, K, V>> {
+        match self._entry(key) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+            Entry::Vacant(entry) => Ok(entry.insert(value)),
+        }
+    }
+
+    fn _shrink_to_fit(&self) {
+        self.shards.iter().for_each(|s| {
+            let mut shard = s.write();
+            let size = shard.len();
+            shard.shrink_to(size, |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            })
+        });
+    }
+
+    fn _retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        self.shards.iter().for_each(|s| {
+            s.write().retain(|(k, v)| f(k, v));
+        });
+    }
+
+    fn _extract_if<F>(&'a self, mut f: F) -> impl Iterator<Item = (K, V)> + 'a
+    where
+        F: FnMut(&K, &mut V) -> bool + 'a,
+    {
+        self.shards.iter().flat_map(move |shard| {
+            shard
+                .write()
+                .extract_if(|(k, v)| f(k, v))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    fn _retain_and_shrink(&self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        self.shards.iter().for_each(|s| {
+            let mut shard = s.write();
+            shard.retain(|(k, v)| f(k, v));
+
+            let len = shard.len();
+            if shard.capacity() > len.saturating_mul(RETAIN_SHRINK_RATIO) {
+                shard.shrink_to(len, |(k, _v)| {
+                    let mut hasher = self.hasher.build_hasher();
+                    k.hash(&mut hasher);
+                    hasher.finish()
+                });
+            }
+        });
+    }
+
+    fn _retain_collect(&self, mut f: impl FnMut(&K, &mut V) -> bool) -> Vec<(K, V)> {
+        let mut removed = Vec::new();
+
+        for shard in self.shards.iter() {
+            removed.extend(shard.write().extract_if(|(k, v)| !f(k, v)));
+        }
+
+        removed
+    }
+
+    fn _retain_count(&self, mut f: impl FnMut(&K, &mut V) -> bool) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                let mut shard = shard.write();
+                let len = shard.len();
+                shard.retain(|(k, v)| f(k, v));
+                len - shard.len()
+            })
+            .sum()
+    }
+
+    fn _split_off_into<T: BuildHasher + Clone>(
+        &self,
+        mut f: impl FnMut(&K, &V) -> bool,
+        target: &DashMap<K, V, T>,
+    ) {
+        for shard in self.shards.iter() {
+            let moved: Vec<_> = shard.write().extract_if(|(k, v)| f(k, v)).collect();
+
+            if !moved.is_empty() {
+                target.insert_many(moved);
+            }
+        }
+    }
+
+    fn _len(&self) -> usize {
+        self.shards.iter().map(|s| s.read().len()).sum()
+    }
+
+    fn _fold<A>(&self, init: A, mut f: impl FnMut(A, &K, &V) -> A) -> A {
+        self.shards.iter().fold(init, |acc, shard| {
+            shard.read().iter().fold(acc, |acc, (k, v)| f(acc, k, v))
+        })
+    }
+
+    fn _sum_values<T>(&self) -> T
+    where
+        T: for<'v> core::iter::Sum<&'v V> + core::iter::Sum<T>,
+    {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().iter().map(|(_k, v)| v).sum::<T>())
+            .sum()
+    }
+
+    fn _capacity(&self) -> usize {
+        self.shards.iter().map(|s| s.read().capacity()).sum()
+    }
+
+    fn _reserve(&self, additional: usize) {
+        let shard_amount = self.shards.len();
+        let per_shard = additional / shard_amount + usize::from(additional % shard_amount != 0);
+
+        for shard in self.shards.iter() {
+            shard.write().reserve(per_shard, |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            });
+        }
+    }
+
+    fn _shard_lengths(&self) -> Vec<usize> {
+        self.shards.iter().map(|s| s.read().len()).collect()
+    }
+
+    fn _alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V)
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        if let Some(mut r) = self.get_mut(key) {
+            util::map_in_place_2(r.pair_mut(), f);
+        }
+    }
+
+    fn _alter_all(&self, mut f: impl FnMut(&K, V) -> V) {
+        self.iter_mut()
+            .for_each(|mut m| util::map_in_place_2(m.pair_mut(), &mut f));
+    }
+
+    fn _compute(
+        &'a self,
+        key: K,
+        f: impl FnOnce(Option<(&K, &mut V)>) -> Option<V>,
+    ) -> Option<RefMut<'a, K, V>> {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].write();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        match shard.entry(
+            hash,
+            |(k, _v)| k == &key,
+            |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(mut entry) => {
+                let (k, v) = entry.get_mut();
+                match f(Some((&*k, v))) {
+                    Some(value) => {
+                        let (k, v) = entry.into_mut();
+                        *v = value;
+                        Some(RefMut::new(guard, k, v))
+                    }
+                    None => {
+                        entry.remove();
+                        None
+                    }
+                }
+            }
+            hash_table::Entry::Vacant(entry) => f(None).map(|value| {
+                let (k, v) = entry.insert((key, value)).into_mut();
+                RefMut::new(guard, k, v)
+            }),
+        }
+    }
+
+    fn _view<Q, R>(&self, key: &Q, f: impl FnOnce(&K, &V) -> R) -> Option<R>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self.get(key).map(|r| {
+            let (k, v) = r.pair();
+            f(k, v)
+        })
+    }
+
+    fn _entry(&'a self, key: K) -> Entry<'a, K, V> {
+        self._entry_hashed(self.hash_u64(&key), key)
+    }
+
+    fn _entry_hashed(&'a self, hash: u64, key: K) -> Entry<'a, K, V> {
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].write();
+
+        self.entry_in_shard(hash, key, shard)
+    }
+
+    #[cfg(feature = "async")]
+    async fn _entry_async(&'a self, key: K) -> Entry<'a, K, V> {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        // SAFETY: The raw lock is only used to acquire the exclusive lock the guard below takes over.
+        let raw = unsafe { self.shards[idx].raw() };
+        core::future::poll_fn(|cx| raw.poll_lock_exclusive(cx)).await;
+        // SAFETY: The shard was exclusively locked just above.
+        let shard = unsafe { self.shards[idx].make_write_guard_unchecked() };
+
+        self.entry_in_shard(hash, key, shard)
+    }
+
+    /// Write-locks two different shards in the order of `DashMap::shards`, and returns the guards
+    /// in the order of the arguments.
+    fn write_two(
+        &self,
+        a: usize,
+        b: usize,
+    ) -> (
+        lock_api::RwLockWriteGuard<'_, lock::RawRwLock, HashMap<K, V>>,
+        lock_api::RwLockWriteGuard<'_, lock::RawRwLock, HashMap<K, V>>,
+    ) {
+        debug_assert_ne!(a, b);
+        if a < b {
+            let shard_a = self.shards[a].write();
+            (shard_a, self.shards[b].write())
+        } else {
+            let shard_b = self.shards[b].write();
+            (self.shards[a].write(), shard_b)
+        }
+    }
+
+    fn entry_in_shard(
+        &'a self,
+        hash: u64,
+        key: K,
+        shard: lock_api::RwLockWriteGuard<'a, lock::RawRwLock, HashMap<K, V>>,
+    ) -> Entry<'a, K, V> {
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        self.entry_in_detached(hash, key, guard, shard)
+    }
+
+    fn entry_in_detached(
+        &'a self,
+        hash: u64,
+        key: K,
+        guard: RwLockWriteGuardDetached<'a>,
+        shard: &'a mut HashMap<K, V>,
+    ) -> Entry<'a, K, V> {
+        match shard.entry(
+            hash,
+            |(k, _v)| k == &key,
+            |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(entry) => {
+                Entry::Occupied(OccupiedEntry::new(guard, key, entry))
+            }
+            hash_table::Entry::Vacant(entry) => Entry::Vacant(VacantEntry::new(guard, key, entry)),
+        }
+    }
+
+    fn _entry_owned(self: &Arc<Self>, key: K) -> OwnedEntry<K, V, S> {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].write();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `OwnedEntry`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        let shard: *mut HashMap<K, V> = shard;
+
+        // SAFETY: `guard` write-locks `shard`, which belongs to this map, and `hash` is the hash of `key`.
+        unsafe {
+            match (*shard).find_mut(hash, |(k, _v)| k == &key) {
+                Some(slot) => OwnedEntry::Occupied(OwnedOccupiedEntry::new(
+                    self.clone(),
+                    guard,
+                    key,
+                    hash,
+                    shard,
+                    slot,
+                )),
+                None => {
+                    OwnedEntry::Vacant(OwnedVacantEntry::new(self.clone(), guard, key, hash, shard))
+                }
+            }
+        }
+    }
+
+    fn _try_entry(&'a self, key: K) -> Option<Entry<'a, K, V>> {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].try_write()?;
+
+        Some(self.entry_in_shard(hash, key, shard))
+    }
+
+    fn _entry_timeout(&'a self, key: K, timeout: Duration) -> Option<Entry<'a, K, V>> {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].try_write_for(timeout)?;
+
+        Some(self.entry_in_shard(hash, key, shard))
+    }
+
+    fn _entry_ref<'q, Q>(&'a self, key: &'q Q) -> EntryRef<'a, 'q, K, Q, V>
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].write();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        match shard.entry(
+            hash,
+            |(k, _v)| key.equivalent(k),
+            |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(entry) => {
+                EntryRef::Occupied(OccupiedEntryRef::new(guard, key, entry))
+            }
+            hash_table::Entry::Vacant(entry) => {
+                EntryRef::Vacant(VacantEntryRef::new(guard, key, entry))
+            }
+        }
+    }
+
+    fn _try_entry_ref<'q, Q>(&'a self, key: &'q Q) -> Option<EntryRef<'a, 'q, K, Q, V>>
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_write() {
+            Some(shard) => shard,
+            None => return None,
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        match shard.entry(
+            hash,
+            |(k, _v)| key.equivalent(k),
+            |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(entry) => {
+                Some(EntryRef::Occupied(OccupiedEntryRef::new(guard, key, entry)))
+            }
+            hash_table::Entry::Vacant(entry) => {
+                Some(EntryRef::Vacant(VacantEntryRef::new(guard, key, entry)))
+            }
+        }
+    }
+
+    fn _clear(&self) {
+        self._retain(|_, _| false)
+    }
+
+    fn _clear_shard(&self, shard_index: usize) {
+        self.shard_at(shard_index).write().clear();
+    }
+
+    fn _shard_len(&self, shard_index: usize) -> usize {
+        self.shard_at(shard_index).read().len()
+    }
+
+    fn shard_at(&self, shard_index: usize) -> &RwLock<HashMap<K, V>> {
+        let shard_amount = self.shards.len();
+        assert!(
+            shard_index < shard_amount,
+            "shard index {shard_index} is out of range for a map with {shard_amount} shards"
+        );
+        &self.shards[shard_index]
+    }
+
+    fn _contains_key<Q>(&'a self, key: &Q) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self._get(key).is_some()
+    }
+
+    /// Returns true as soon as one of `keys` is found whose presence in the map equals `present`.
+    fn _contains_matching<'k, Q, I>(&self, keys: I, present: bool) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized + 'k,
+        I: IntoIterator<Item = &'k Q>,
+    {
+        let mut lookups: Vec<_> = keys
+            .into_iter()
+            .map(|key| {
+                let hash = self.hash_u64(&key);
+                (self.determine_shard(hash as usize), hash, key)
+            })
+            .collect();
+        lookups.sort_unstable_by_key(|&(idx, ..)| idx);
+
+        let mut rest = &lookups[..];
+        while let Some(&(idx, ..)) = rest.first() {
+            let len = rest.iter().take_while(|lookup| lookup.0 == idx).count();
+            let (group, tail) = rest.split_at(len);
+            rest = tail;
+
+            let shard = self.shards[idx].read();
+            if group.iter().any(|&(_idx, hash, key)| {
+                shard.find(hash, |(k, _v)| key.equivalent(k)).is_some() == present
+            }) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn _is_empty(&self) -> bool {
+        self._len() == 0
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher + Clone> fmt::Debug
+    for DashMap<K, V, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pmap = f.debug_map();
+
+        for r in self {
+            let (k, v) = r.pair();
+
+            pmap.entry(k, v);
+        }
+
+        pmap.finish()
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> Shl<(K, V)> for &'a DashMap<K, V, S> {
+    type Output = Option<V>;
+
+    fn shl(self, pair: (K, V)) -> Self::Output {
+        self.insert(pair.0, pair.1)
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> Shr<&Q> for &'a DashMap<K, V, S>
+where
+    Q: Hash + Equivalent<K> + ?Sized,
+{
+    type Output = Ref<'a, K, V>;
+
+    fn shr(self, key: &Q) -> Self::Output {
+        self.get(key).unwrap()
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> BitOr<&Q> for &'a DashMap<K, V, S>
+where
+    Q: Hash + Equivalent<K> + ?Sized,
+{
+    type Output = RefMut<'a, K, V>;
+
+    fn bitor(self, key: &Q) -> Self::Output {
+        self.get_mut(key).unwrap()
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> Sub<&Q> for &'a DashMap<K, V, S>
+where
+    Q: Hash + Equivalent<K> + ?Sized,
+{
+    type Output = Option<(K, V)>;
+
+    fn sub(self, key: &Q) -> Self::Output {
+        self.remove(key)
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> BitAnd<&Q> for &'a DashMap<K, V, S>
+where
+    Q: Hash + Equivalent<K> + ?Sized,
+{
+    type Output = bool;
+
+    fn bitand(self, key: &Q) -> Self::Output {
+        self.contains_key(key)
+    }
+}
+
+/// Two maps are equal if they have the same length and every key of one maps to an equal value
+/// in the other. The comparison stops at the first difference.
+///
+/// Shards are read-locked one at a time while probing, so comparing maps that are being
+/// modified concurrently is safe, but the result is unspecified.
+impl<'a, K: 'a + Eq + Hash, V: 'a + PartialEq, S: BuildHasher + Clone> PartialEq
+    for DashMap<K, V, S>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.iter().all(|r| {
+                other
+                    .get(r.key())
+                    .map_or(false, |ro| r.value() == ro.value())
+            })
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a + Eq, S: BuildHasher + Clone> Eq for DashMap<K, V, S> {}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Clone> IntoIterator for DashMap<K, V, S> {
+    type Item = (K, V);
+
+    type IntoIter = OwningIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        OwningIter::new(self)
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher + Clone> IntoIterator for &'a DashMap<K, V, S> {
+    type Item = RefMulti<'a, K, V>;
+
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Clone> Extend<(K, V)> for DashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, intoiter: I) {
+        for pair in intoiter.into_iter() {
+            self.insert(pair.0, pair.1);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Clone + Default> FromIterator<(K, V)> for DashMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(intoiter: I) -> Self {
+        let iter = intoiter.into_iter();
+        // The constructor spreads the capacity over the shards, so this reserves up front
+        // for at least the entries the iterator promises to yield.
+        let mut map = DashMap::with_capacity_and_hasher(iter.size_hint().0, S::default());
+
+        map.extend(iter);
+
+        map
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Clone> From<std::collections::HashMap<K, V, S>>
+    for DashMap<K, V, S>
+{
+    fn from(map: std::collections::HashMap<K, V, S>) -> Self {
+        let mut dashmap = DashMap::with_capacity_and_hasher(map.len(), map.hasher().clone());
+
+        dashmap.extend(map);
+
+        dashmap
+    }
+}
+
+#[cfg(feature = "typesize")]
+impl<K, V, S> typesize::TypeSize for DashMap<K, V, S>
+where
+    K: typesize::TypeSize + Eq + Hash,
+    V: typesize::TypeSize,
+    S: typesize::TypeSize + Clone + BuildHasher,
+{
+    fn extra_size(&self) -> usize {
+        let shards_extra_size: usize = self
+            .shards
+            .iter()
+            .map(|shard_lock| {
+                core::mem::size_of::<CachePadded<RwLock<HashMap<K, V>>>>()
+                    + shard_lock.read().extra_size()
+            })
+            .sum();
+
+        self.hasher.extra_size() + shards_extra_size
+    }
+
+    typesize::if_typesize_details! {
+        fn get_collection_item_count(&self) -> Option<usize> {
+            Some(self.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DashMap, Upsert};
+    use std::collections::hash_map::RandomState;
+
+    #[test]
+    fn test_basic() {
+        let dm = DashMap::new();
+
+        dm.insert(0, 0);
+
+        assert_eq!(dm.get(&0).unwrap().value(), &0);
+    }
+
+    #[test]
+    fn test_default() {
+        let dm: DashMap<u32, u32> = DashMap::default();
+
+        dm.insert(0, 0);
+
+        assert_eq!(dm.get(&0).unwrap().value(), &0);
+    }
+
+    #[test]
+    fn test_equal() {
+        let dm1 = DashMap::new();
+        let dm2 = DashMap::new();
+        assert_eq!(dm1, dm2);
+
+        dm1.insert(0, "Hello, world!");
+        assert_ne!(dm1, dm2);
+
+        dm1.insert(1, "Goodbye, world!");
+        assert_ne!(dm1, dm2);
+
+        dm2.insert(0, "Hello, world!");
+        assert_ne!(dm1, dm2);
+
+        dm2.insert(1, "Goodbye, world!");
+        assert_eq!(dm1, dm2);
+    }
+
+    #[test]
+    fn test_multiple_hashes() {
+        let dm: DashMap<u32, u32> = DashMap::default();
+
+        for i in 0..100 {
+            dm.insert(0, i);
+
+            dm.insert(i, i);
         }
-        Ok(())
-    }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: 'a + BuildHasher + Clone> DashMap<K, V, S> {
-    fn _insert(&self, key: K, value: V) -> Option<V> {
-        match self.entry(key) {
-            Entry::Occupied(mut o) => Some(o.insert(value)),
-            Entry::Vacant(v) => {
-                v.insert(value);
-                None
-            }
+        for i in 1..100 {
+            let r = dm.get(&i).unwrap();
+
+            assert_eq!(i, *r.value());
+
+            assert_eq!(i, *r.key());
         }
+
+        let r = dm.get(&0).unwrap();
+
+        assert_eq!(99, *r.value());
     }
 
-    fn _remove<Q>(&self, key: &Q) -> Option<(K, V)>
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_more_complex_values() {
+        #[derive(Hash, PartialEq, Debug, Clone)]
 
-        let idx = self.determine_shard(hash as usize);
+        struct T0 {
+            s: String,
+            u: u8,
+        }
 
-        let mut shard = self.shards[idx].write();
+        let dm = DashMap::new();
 
-        if let Ok(entry) = shard.find_entry(hash, |(k, _v)| key.equivalent(k)) {
-            let ((k, v), _) = entry.remove();
-            Some((k, v))
-        } else {
-            None
+        let range = 0..10;
+
+        for i in range {
+            let t = T0 {
+                s: i.to_string(),
+                u: i as u8,
+            };
+
+            dm.insert(i, t.clone());
+
+            assert_eq!(&t, dm.get(&i).unwrap().value());
         }
     }
 
-    fn _remove_if<Q>(&self, key: &Q, f: impl FnOnce(&K, &V) -> bool) -> Option<(K, V)>
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
-
-        let idx = self.determine_shard(hash as usize);
+    #[test]
+    fn test_different_hashers_randomstate() {
+        let dm_hm_default: DashMap<u32, u32, RandomState> =
+            DashMap::with_hasher(RandomState::new());
 
-        let mut shard = self.shards[idx].write();
+        for i in 0..10 {
+            dm_hm_default.insert(i, i);
 
-        if let Ok(entry) = shard.find_entry(hash, |(k, _v)| key.equivalent(k)) {
-            let (k, v) = entry.get();
-            if f(k, v) {
-                let ((k, v), _) = entry.remove();
-                Some((k, v))
-            } else {
-                None
-            }
-        } else {
-            None
+            assert_eq!(i, *dm_hm_default.get(&i).unwrap().value());
         }
     }
 
-    fn _remove_if_mut<Q>(&self, key: &Q, f: impl FnOnce(&K, &mut V) -> bool) -> Option<(K, V)>
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_map_view() {
+        let dm = DashMap::new();
 
-        let idx = self.determine_shard(hash as usize);
+        let vegetables: [String; 4] = [
+            "Salad".to_string(),
+            "Beans".to_string(),
+            "Potato".to_string(),
+            "Tomato".to_string(),
+        ];
 
-        let mut shard = self.shards[idx].write();
+        // Give it some values
+        dm.insert(0, "Banana".to_string());
+        dm.insert(4, "Pear".to_string());
+        dm.insert(9, "Potato".to_string());
+        dm.insert(12, "Chicken".to_string());
 
-        if let Ok(mut entry) = shard.find_entry(hash, |(k, _v)| key.equivalent(k)) {
-            let (k, v) = entry.get_mut();
-            if f(k, v) {
-                let ((k, v), _) = entry.remove();
-                Some((k, v))
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+        let potato_vegetableness = dm.view(&9, |_, v| vegetables.contains(v));
+        assert_eq!(potato_vegetableness, Some(true));
+
+        let chicken_vegetableness = dm.view(&12, |_, v| vegetables.contains(v));
+        assert_eq!(chicken_vegetableness, Some(false));
+
+        let not_in_map = dm.view(&30, |_k, _v| false);
+        assert_eq!(not_in_map, None);
     }
 
-    fn _iter(&'a self) -> Iter<'a, K, V> {
-        Iter::new(self)
+    #[test]
+    fn test_shard_index_of() {
+        let map = DashMap::with_shard_amount(8);
+        let mut expected = vec![0; map.shard_count()];
+        for i in 0..64 {
+            map.insert(i, i);
+            expected[map.shard_index_of(&i)] += 1;
+        }
+
+        assert_eq!(map.shard_lengths(), expected);
     }
 
-    fn _iter_mut(&'a self) -> IterMut<'a, K, V> {
-        IterMut::new(self)
+    #[test]
+    fn test_hasher_matches_internal_hashing() {
+        use core::hash::{BuildHasher, Hash, Hasher};
+
+        let map: DashMap<String, i32> = DashMap::new();
+        map.insert(String::from("key"), 1);
+
+        let mut hasher = map.hasher().build_hasher();
+        "key".hash(&mut hasher);
+        let hash = hasher.finish();
+
+        assert_eq!(hash, map.hash_key("key"));
+        assert_eq!(*map.get_hashed(hash, "key").unwrap(), 1);
+        assert!(core::ptr::eq(map.hasher(), &map.hasher));
     }
 
-    fn _get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_try_get() {
+        {
+            let map = DashMap::new();
+            map.insert("Johnny", 21);
 
-        let idx = self.determine_shard(hash as usize);
+            assert_eq!(*map.try_get("Johnny").unwrap(), 21);
 
-        let shard = self.shards[idx].read();
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
-        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+            let _result1_locking = map.get_mut("Johnny");
 
-        if let Some((k, v)) = shard.find(hash, |(k, _v)| key.equivalent(k)) {
-            Some(Ref::new(guard, k, v))
-        } else {
-            None
+            let result2 = map.try_get("Johnny");
+            assert!(result2.is_locked());
         }
-    }
 
-    fn _get_mut<Q>(&'a self, key: &Q) -> Option<RefMut<'a, K, V>>
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+        {
+            let map = DashMap::new();
+            map.insert("Johnny", 21);
 
-        let idx = self.determine_shard(hash as usize);
+            *map.try_get_mut("Johnny").unwrap() += 1;
+            assert_eq!(*map.get("Johnny").unwrap(), 22);
 
-        let shard = self.shards[idx].write();
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+            let _result1_locking = map.get("Johnny");
 
-        if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| key.equivalent(k)) {
-            Some(RefMut::new(guard, k, v))
-        } else {
-            None
+            let result2 = map.try_get_mut("Johnny");
+            assert!(result2.is_locked());
         }
     }
 
-    fn _try_get<Q>(&'a self, key: &Q) -> TryResult<Ref<'a, K, V>>
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_try_get_while_reading() {
+        let map = DashMap::new();
+        map.insert("Johnny", 21);
 
-        let idx = self.determine_shard(hash as usize);
+        let _result1_reading = map.get("Johnny");
 
-        let shard = match self.shards[idx].try_read() {
-            Some(shard) => shard,
-            None => return TryResult::Locked,
+        assert!(map.try_get("Johnny").is_present());
+        assert!(map.try_get("Alice").is_absent());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_entry_async() {
+        use std::future::Future;
+        use std::pin::pin;
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Noop;
+
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = Context::from_waker(&waker);
+
+        let map = DashMap::new();
+        map.insert(1, 1);
+
+        // Another task on the same thread holds the shard, so waiting must not block the thread.
+        let held = map.get_mut(&1).unwrap();
+        let mut fut = pin!(map.entry_async(1));
+        assert!(fut.as_mut().poll(&mut cx).is_pending());
+        assert!(fut.as_mut().poll(&mut cx).is_pending());
+        drop(held);
+
+        let Poll::Ready(entry) = fut.as_mut().poll(&mut cx) else {
+            panic!("the shard is no longer locked")
         };
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
-        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+        *entry.or_insert(0) += 1;
 
-        if let Some((k, v)) = shard.find(hash, |(k, _v)| key.equivalent(k)) {
-            TryResult::Present(Ref::new(guard, k, v))
-        } else {
-            TryResult::Absent
+        assert_eq!(*map.get(&1).unwrap(), 2);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_entry_async_current_thread() {
+        use std::future::Future;
+        use std::pin::pin;
+        use std::sync::{Arc, Barrier};
+        use std::task::{Context, Poll, Wake, Waker};
+        use std::thread::{self, Thread};
+        use std::time::Duration;
+
+        // A current-thread executor: the thread sleeps until the task is woken.
+        struct Unpark(Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
         }
+
+        let map = DashMap::new();
+        map.insert(1, 1);
+
+        let locked = Barrier::new(2);
+        thread::scope(|s| {
+            s.spawn(|| {
+                let _held = map.get_mut(&1).unwrap();
+                locked.wait();
+                thread::sleep(Duration::from_millis(100));
+            });
+            locked.wait();
+
+            let waker = Waker::from(Arc::new(Unpark(thread::current())));
+            let mut cx = Context::from_waker(&waker);
+            let mut fut = pin!(map.entry_async(1));
+
+            let mut polls = 0;
+            let entry = loop {
+                polls += 1;
+                match fut.as_mut().poll(&mut cx) {
+                    Poll::Ready(entry) => break entry,
+                    Poll::Pending => thread::park(),
+                }
+            };
+            *entry.or_insert(0) += 1;
+
+            // Waiting for the other thread must not spin the executor.
+            assert!(polls < 10, "polled {polls} times");
+        });
+
+        assert_eq!(*map.get(&1).unwrap(), 2);
     }
 
-    fn _try_get_mut<Q>(&'a self, key: &Q) -> TryResult<RefMut<'a, K, V>>
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_entry_timeout() {
+        use std::sync::Barrier;
+        use std::time::Duration;
+
+        let map = DashMap::new();
+        map.insert(1, 1);
+
+        let locked = Barrier::new(2);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let writer = map.get_mut(&1).unwrap();
+                locked.wait();
+                std::thread::sleep(Duration::from_millis(50));
+                drop(writer);
+            });
+
+            locked.wait();
+            assert!(map.try_entry(1).is_none());
+            assert!(map.entry_timeout(1, Duration::from_millis(1)).is_none());
+
+            let Some(entry) = map.entry_timeout(1, Duration::from_secs(60)) else {
+                panic!("the writer releases the shard")
+            };
+            *entry.or_insert(0) += 1;
+        });
 
-        let idx = self.determine_shard(hash as usize);
+        assert_eq!(*map.get(&1).unwrap(), 2);
+    }
 
-        let shard = match self.shards[idx].try_write() {
-            Some(shard) => shard,
-            None => return TryResult::Locked,
-        };
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+    #[test]
+    fn test_hashed() {
+        let first = DashMap::new();
+        let second = DashMap::with_hasher(first.hasher().clone());
 
-        if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| key.equivalent(k)) {
-            TryResult::Present(RefMut::new(guard, k, v))
-        } else {
-            TryResult::Absent
+        let hash = first.hash_key("apple");
+        assert_eq!(hash, second.hash_key("apple"));
+
+        assert_eq!(first.insert_hashed(hash, "apple", 1), None);
+        assert_eq!(first.insert_hashed(hash, "apple", 2), Some(1));
+        *second.entry_hashed(hash, "apple").or_insert(10) += 1;
+
+        assert_eq!(*first.get("apple").unwrap(), 2);
+        assert_eq!(*second.get_hashed(hash, "apple").unwrap(), 11);
+        assert!(first.get_hashed(hash, "pear").is_none());
+
+        assert_eq!(first.remove_hashed(hash, "apple"), Some(("apple", 2)));
+        assert!(first.is_empty());
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let dm = DashMap::with_shard_amount(4);
+        dm.insert(0, 0);
+
+        let overwritten = dm.insert_many((0..100).map(|i| (i % 50, i)));
+
+        assert_eq!(overwritten, 51);
+        assert_eq!(dm.len(), 50);
+        for i in 0..50 {
+            assert_eq!(*dm.get(&i).unwrap(), i + 50);
         }
+        assert_eq!(dm.insert_many(std::iter::empty()), 0);
+    }
+
+    #[test]
+    fn test_remove_if_mut() {
+        let dm = DashMap::new();
+        dm.insert(1, vec![1, 2]);
+
+        assert_eq!(dm.remove_if_mut(&2, |_, _| true), None);
+        assert_eq!(
+            dm.remove_if_mut(&1, |_, v| {
+                v.pop();
+                v.is_empty()
+            }),
+            None
+        );
+        assert_eq!(*dm.get(&1).unwrap(), vec![1]);
+        assert_eq!(
+            dm.remove_if_mut(&1, |_, v| {
+                v.pop();
+                v.is_empty()
+            }),
+            Some((1, vec![]))
+        );
+        assert!(dm.is_empty());
+    }
+
+    #[test]
+    fn test_alter() {
+        let dm = DashMap::new();
+        dm.insert(1, vec![1]);
+        dm.insert(2, vec![2]);
+
+        dm.alter(&3, |_, _| unreachable!());
+        assert!(!dm.contains_key(&3));
+
+        dm.alter(&1, |k, mut v| {
+            v.push(*k + 1);
+            v
+        });
+        assert_eq!(*dm.get(&1).unwrap(), [1, 2]);
+
+        dm.alter_all(|k, v| v.into_iter().map(|x| x * k).collect());
+        assert_eq!(*dm.get(&1).unwrap(), [1, 2]);
+        assert_eq!(*dm.get(&2).unwrap(), [4]);
     }
 
-    fn _shrink_to_fit(&self) {
-        self.shards.iter().for_each(|s| {
-            let mut shard = s.write();
-            let size = shard.len();
-            shard.shrink_to(size, |(k, _v)| {
-                let mut hasher = self.hasher.build_hasher();
-                k.hash(&mut hasher);
-                hasher.finish()
-            })
+    #[test]
+    fn test_compute() {
+        let dm = DashMap::new();
+
+        assert!(dm.compute(1, |e| e.map(|(_, v)| *v)).is_none());
+        assert!(dm.is_empty());
+
+        assert_eq!(*dm.compute(1, |e| e.map_or(Some(1), |_| None)).unwrap(), 1);
+
+        let mut seen = None;
+        let r = dm.compute(1, |e| {
+            let (k, v) = e.unwrap();
+            *v += 1;
+            seen = Some((*k, *v));
+            Some(*v * 10)
         });
+        assert_eq!(*r.unwrap(), 20);
+        assert_eq!(seen, Some((1, 2)));
+
+        assert!(dm.compute(1, |_| None).is_none());
+        assert!(dm.is_empty());
     }
 
-    fn _retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) {
-        self.shards.iter().for_each(|s| {
-            s.write().retain(|(k, v)| f(k, v));
-        });
+    #[test]
+    fn test_reserve() {
+        let map = DashMap::with_shard_amount(8);
+        for i in 0..10 {
+            map.insert(i, i);
+        }
+
+        map.reserve(1001);
+
+        assert!(map.capacity() >= 10 + 1001);
+        let lengths = map.shard_lengths();
+        for (shard, len) in map.shards().iter().zip(lengths) {
+            assert!(shard.read().capacity() >= len + 126);
+        }
     }
 
-    fn _len(&self) -> usize {
-        self.shards.iter().map(|s| s.read().len()).sum()
+    #[test]
+    fn test_clear_shard() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+
+        let lengths = map.shard_lengths();
+        let busiest = (0..map.shard_count()).max_by_key(|&i| lengths[i]).unwrap();
+        assert_eq!(map.shard_len(busiest), lengths[busiest]);
+
+        map.clear_shard(busiest);
+
+        assert_eq!(map.shard_len(busiest), 0);
+        assert_eq!(map.len(), 100 - lengths[busiest]);
     }
 
-    fn _capacity(&self) -> usize {
-        self.shards.iter().map(|s| s.read().capacity()).sum()
+    #[test]
+    #[should_panic(expected = "shard index 4 is out of range for a map with 4 shards")]
+    fn test_clear_shard_out_of_range() {
+        let map: DashMap<i32, i32> = DashMap::with_shard_amount(4);
+        map.clear_shard(4);
     }
 
-    fn _alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V)
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        if let Some(mut r) = self.get_mut(key) {
-            util::map_in_place_2(r.pair_mut(), f);
+    #[test]
+    fn test_shrink_to_fit() {
+        let map = DashMap::new();
+
+        for i in 0..10_000 {
+            map.insert(i, i);
         }
+        map.retain(|k, _v| k % 100 == 0);
+
+        let before = map.capacity();
+        map.shrink_to_fit();
+
+        assert!(map.capacity() < before);
+        assert!(map.capacity() >= 100);
+        assert_eq!(map.len(), 100);
     }
 
-    fn _alter_all(&self, mut f: impl FnMut(&K, V) -> V) {
-        self.iter_mut()
-            .for_each(|mut m| util::map_in_place_2(m.pair_mut(), &mut f));
+    #[test]
+    fn test_shard_amount() {
+        assert_eq!(DashMap::<i32, i32>::with_shard_amount(2).shard_count(), 2);
+        assert_eq!(
+            DashMap::<i32, i32>::with_hasher_and_shard_amount(RandomState::new(), 64).shard_count(),
+            64
+        );
     }
 
-    fn _view<Q, R>(&self, key: &Q, f: impl FnOnce(&K, &V) -> R) -> Option<R>
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        self.get(key).map(|r| {
-            let (k, v) = r.pair();
-            f(k, v)
-        })
+    #[test]
+    #[should_panic(expected = "shard amount must be a power of two, got 12")]
+    fn test_shard_amount_not_power_of_two() {
+        DashMap::<i32, i32>::with_shard_amount(12);
     }
 
-    fn _entry(&'a self, key: K) -> Entry<'a, K, V> {
-        let hash = self.hash_u64(&key);
+    #[test]
+    #[should_panic(expected = "shard amount must be greater than 1, got 0")]
+    fn test_shard_amount_zero() {
+        DashMap::<i32, i32>::with_shard_amount(0);
+    }
 
-        let idx = self.determine_shard(hash as usize);
+    #[test]
+    fn test_shard_lengths() {
+        #[derive(Clone, Default)]
+        struct Colliding;
 
-        let shard = self.shards[idx].write();
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        impl std::hash::BuildHasher for Colliding {
+            type Hasher = Colliding;
 
-        match shard.entry(
-            hash,
-            |(k, _v)| k == &key,
-            |(k, _v)| {
-                let mut hasher = self.hasher.build_hasher();
-                k.hash(&mut hasher);
-                hasher.finish()
-            },
-        ) {
-            hash_table::Entry::Occupied(entry) => {
-                Entry::Occupied(OccupiedEntry::new(guard, key, entry))
+            fn build_hasher(&self) -> Colliding {
+                Colliding
             }
-            hash_table::Entry::Vacant(entry) => Entry::Vacant(VacantEntry::new(guard, key, entry)),
         }
-    }
 
-    fn _try_entry(&'a self, key: K) -> Option<Entry<'a, K, V>> {
-        let hash = self.hash_u64(&key);
+        impl std::hash::Hasher for Colliding {
+            fn finish(&self) -> u64 {
+                0
+            }
 
-        let idx = self.determine_shard(hash as usize);
+            fn write(&mut self, _bytes: &[u8]) {}
+        }
 
-        let shard = match self.shards[idx].try_write() {
-            Some(shard) => shard,
-            None => return None,
-        };
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        let spread = DashMap::with_shard_amount(8);
+        let skewed = DashMap::with_hasher_and_shard_amount(Colliding, 8);
+        for i in 0..100 {
+            spread.insert(i, i);
+            skewed.insert(i, i);
+        }
 
-        match shard.entry(
-            hash,
-            |(k, _v)| k == &key,
-            |(k, _v)| {
-                let mut hasher = self.hasher.build_hasher();
-                k.hash(&mut hasher);
-                hasher.finish()
-            },
-        ) {
-            hash_table::Entry::Occupied(entry) => {
-                Some(Entry::Occupied(OccupiedEntry::new(guard, key, entry)))
-            }
-            hash_table::Entry::Vacant(entry) => {
-                Some(Entry::Vacant(VacantEntry::new(guard, key, entry)))
+        let lengths = spread.shard_lengths();
+        assert_eq!(spread.shard_count(), 8);
+        assert_eq!(lengths.len(), 8);
+        assert_eq!(lengths.iter().sum::<usize>(), 100);
+        assert!(lengths.iter().all(|&len| len < 100));
+
+        let lengths = skewed.shard_lengths();
+        assert_eq!(lengths.iter().sum::<usize>(), 100);
+        assert_eq!(lengths.iter().filter(|&&len| len > 0).count(), 1);
+        assert!(skewed.capacity() >= 100);
+    }
+
+    #[test]
+    fn test_try_insert_race() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let map = DashMap::new();
+        let winners = AtomicUsize::new(0);
+
+        std::thread::scope(|s| {
+            for worker in 0..16 {
+                let (map, winners) = (&map, &winners);
+                s.spawn(move || match map.try_insert("job", worker) {
+                    Ok(_) => {
+                        winners.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(err) => assert_eq!(err.value, worker),
+                });
             }
+        });
+
+        assert_eq!(winners.load(Ordering::Relaxed), 1);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let manual = DashMap::new();
+        for i in 0..1000 {
+            manual.insert(i, i * 3);
         }
+
+        let collected: DashMap<i32, i32> = (0..1000).map(|i| (i, i * 3)).collect();
+
+        assert_eq!(collected, manual);
+        assert!(collected.capacity() >= 1000);
     }
 
-    fn _entry_ref<'q, Q>(&'a self, key: &'q Q) -> EntryRef<'a, 'q, K, Q, V>
-    where
-        Q: Hash + Equivalent<K>,
-    {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_try_insert() {
+        let map = DashMap::new();
 
-        let idx = self.determine_shard(hash as usize);
+        let inserted = map.try_insert("Johnny", 21).unwrap();
+        assert!(map.try_get("Johnny").is_locked());
+        drop(inserted);
 
-        let shard = self.shards[idx].write();
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        let Err(err) = map.try_insert("Johnny", 42) else {
+            panic!("key is already present")
+        };
+        assert_eq!(
+            (*err.entry.key(), *err.entry.get(), err.value),
+            ("Johnny", 21, 42)
+        );
+        assert!(map.try_get("Johnny").is_locked());
+        drop(err);
+
+        assert_eq!(*map.get("Johnny").unwrap(), 21);
+        assert_eq!(map.len(), 1);
+    }
 
-        match shard.entry(
-            hash,
-            |(k, _v)| key.equivalent(k),
-            |(k, _v)| {
-                let mut hasher = self.hasher.build_hasher();
-                k.hash(&mut hasher);
-                hasher.finish()
-            },
-        ) {
-            hash_table::Entry::Occupied(entry) => {
-                EntryRef::Occupied(OccupiedEntryRef::new(guard, key, entry))
-            }
-            hash_table::Entry::Vacant(entry) => {
-                EntryRef::Vacant(VacantEntryRef::new(guard, key, entry))
-            }
-        }
+    #[test]
+    fn test_insert_and_get() {
+        let map = DashMap::new();
+
+        let (old, mut r) = map.insert_and_get("Johnny", 21);
+        assert_eq!(old, None);
+        *r += 1;
+        drop(r);
+
+        let (old, r) = map.insert_and_get_ref("Johnny", 30);
+        assert_eq!(old, Some(22));
+        assert_eq!(*r, 30);
+        assert!(map.try_get("Johnny").is_present());
+        assert!(map.try_get_mut("Johnny").is_locked());
+        drop(r);
+
+        assert_eq!(map.len(), 1);
     }
 
-    fn _try_entry_ref<'q, Q>(&'a self, key: &'q Q) -> Option<EntryRef<'a, 'q, K, Q, V>>
-    where
-        Q: Hash + Equivalent<K>,
-    {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_modify_or_insert() {
+        let map = DashMap::new();
 
-        let idx = self.determine_shard(hash as usize);
+        assert!(!map.modify_or_insert("Johnny", |_, _| unreachable!(), |k| k.len()));
+        assert!(map.modify_or_insert("Johnny", |k, v| *v += k.len(), |_| unreachable!()));
 
-        let shard = match self.shards[idx].try_write() {
-            Some(shard) => shard,
-            None => return None,
-        };
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        assert_eq!(*map.get("Johnny").unwrap(), 12);
+    }
 
-        match shard.entry(
-            hash,
-            |(k, _v)| key.equivalent(k),
-            |(k, _v)| {
-                let mut hasher = self.hasher.build_hasher();
-                k.hash(&mut hasher);
-                hasher.finish()
-            },
-        ) {
-            hash_table::Entry::Occupied(entry) => {
-                Some(EntryRef::Occupied(OccupiedEntryRef::new(guard, key, entry)))
-            }
-            hash_table::Entry::Vacant(entry) => {
-                Some(EntryRef::Vacant(VacantEntryRef::new(guard, key, entry)))
-            }
+    #[test]
+    fn test_range() {
+        let map = DashMap::with_shard_amount(8);
+        for i in (0..200).rev() {
+            map.insert(i, i.to_string());
         }
+
+        let keys: Vec<i32> = map.range(50..60).map(|r| *r.key()).collect();
+        assert_eq!(keys, (50..60).collect::<Vec<_>>());
+
+        assert_eq!(map.range(..=3).count(), 4);
+        assert_eq!(map.range(195..).count(), 5);
+        assert_eq!(map.range(300..).count(), 0);
+
+        let held = map.range(0..1).next().unwrap();
+        assert_eq!(held.value(), "0");
+        assert!(map.try_get_mut(&0).is_locked());
+        drop(held);
+        assert!(map.try_get_mut(&0).is_present());
     }
 
-    fn _clear(&self) {
-        self._retain(|_, _| false)
+    #[test]
+    fn test_swap() {
+        const KEYS: u64 = 32;
+
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..KEYS {
+            map.insert(i, i);
+        }
+
+        std::thread::scope(|s| {
+            for t in 0..8 {
+                let map = &map;
+                s.spawn(move || {
+                    let mut state = t + 1u64;
+                    for _ in 0..10_000 {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                        let a = (state >> 33) % KEYS;
+                        let b = (state >> 13) % KEYS;
+                        assert!(map.swap(&a, &b));
+                    }
+                });
+            }
+        });
+
+        let mut values: Vec<_> = map.iter().map(|r| *r.value()).collect();
+        values.sort_unstable();
+        assert!(values.into_iter().eq(0..KEYS));
+
+        assert!(map.swap(&3, &3));
+        assert!(!map.swap(&3, &KEYS));
+        assert!(!map.swap(&KEYS, &3));
     }
 
-    fn _contains_key<Q>(&'a self, key: &Q) -> bool
-    where
-        Q: Hash + Equivalent<K> + ?Sized,
-    {
-        self._get(key).is_some()
+    #[test]
+    fn test_merge_from() {
+        let map = DashMap::with_shard_amount(4);
+        let other = DashMap::with_shard_amount(8);
+        for i in 0..50 {
+            map.insert(i, vec![i]);
+        }
+        for i in 25..75 {
+            other.insert(i, vec![i + 100]);
+        }
+
+        let mut conflicts = 0;
+        map.merge_from_ref(&other, |_, mut old, new| {
+            conflicts += 1;
+            old.extend(new);
+            old
+        });
+        assert_eq!(conflicts, 25);
+        assert_eq!(other.len(), 50);
+        assert_eq!(*map.get(&30).unwrap(), [30, 130]);
+
+        let mut conflicts = 0;
+        map.merge_from(other, |_, old, new| {
+            conflicts += 1;
+            assert_eq!(old.len(), 1 + (old[0] < 50) as usize);
+            new
+        });
+        assert_eq!(conflicts, 50);
+        assert_eq!(map.len(), 75);
+        assert!(map
+            .iter()
+            .all(|r| *r.value() == [*r.key() + 100] || *r.key() < 25));
     }
 
-    fn _is_empty(&self) -> bool {
-        self._len() == 0
+    #[test]
+    fn test_merge_from_panic() {
+        let map = DashMap::new();
+        map.insert(1, 1);
+        map.insert(2, 2);
+        let other = DashMap::new();
+        other.insert(1, 10);
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map.merge_from_ref(&other, |_, _, _| panic!("resolve failed"));
+        }));
+        assert!(res.is_err());
+
+        // The conflicting key is gone, the rest of both maps is untouched.
+        assert!(!map.contains_key(&1));
+        assert_eq!(*map.get(&2).unwrap(), 2);
+        assert_eq!(*other.get(&1).unwrap(), 10);
     }
-}
 
-impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher + Clone> fmt::Debug
-    for DashMap<K, V, S>
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut pmap = f.debug_map();
+    #[test]
+    fn test_entry_many() {
+        let map = DashMap::with_shard_amount(4);
+        map.insert(0, vec![0]);
+
+        let mut visited = Vec::new();
+        map.entry_many((0..32).chain(0..32), |entry| {
+            visited.push(*entry.key());
+            entry.or_default().push(1);
+        });
 
-        for r in self {
-            let (k, v) = r.pair();
+        assert_eq!(visited.len(), 64);
+        let shard_of = |key: &i32| map.determine_shard(map.hash_u64(key) as usize);
+        assert!(visited
+            .windows(2)
+            .all(|w| shard_of(&w[0]) <= shard_of(&w[1])));
 
-            pmap.entry(k, v);
-        }
+        assert_eq!(map.len(), 32);
+        assert_eq!(*map.get(&0).unwrap(), [0, 1, 1]);
+        assert!(map.iter().all(|r| r.len() == 2 || *r.key() == 0));
 
-        pmap.finish()
+        // The shard lock is released once its batch is done.
+        assert!(map.try_get_mut(&0).is_present());
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> Shl<(K, V)> for &'a DashMap<K, V, S> {
-    type Output = Option<V>;
+    #[test]
+    fn test_move_key() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..64 {
+            map.insert(i, i * 10);
+        }
 
-    fn shl(self, pair: (K, V)) -> Self::Output {
-        self.insert(pair.0, pair.1)
+        let shard_of = |key: &i32| map.determine_shard(map.hash_u64(key) as usize);
+        let same = (1..64).find(|k| shard_of(k) == shard_of(&0)).unwrap();
+        let other = (1..64).find(|k| shard_of(k) != shard_of(&0)).unwrap();
+
+        assert!(map.move_key(&0, same));
+        assert_eq!(*map.get(&same).unwrap(), 0);
+        assert!(map.move_key(&same, other));
+        assert_eq!(*map.get(&other).unwrap(), 0);
+        assert!(map.move_key(&other, 100));
+        assert!(map.move_key(&100, 100));
+        assert!(!map.move_key(&0, 1));
+
+        assert_eq!(map.len(), 62);
+        assert_eq!(*map.get(&100).unwrap(), 0);
+        assert!(!map.contains_key(&0) && !map.contains_key(&same) && !map.contains_key(&other));
     }
-}
-
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> Shr<&Q> for &'a DashMap<K, V, S>
-where
-    Q: Hash + Equivalent<K> + ?Sized,
-{
-    type Output = Ref<'a, K, V>;
 
-    fn shr(self, key: &Q) -> Self::Output {
-        self.get(key).unwrap()
+    #[test]
+    fn test_replace_existing() {
+        let map = DashMap::new();
+        map.insert(String::from("a"), 1);
+
+        assert_eq!(map.replace_existing("a", 2), Ok(1));
+        assert_eq!(map.replace_existing("b", 3), Err(3));
+        assert_eq!(map.len(), 1);
+        assert_eq!(*map.get("a").unwrap(), 2);
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> BitOr<&Q> for &'a DashMap<K, V, S>
-where
-    Q: Hash + Equivalent<K> + ?Sized,
-{
-    type Output = RefMut<'a, K, V>;
+    #[test]
+    fn test_insert_if_absent() {
+        let map = DashMap::new();
+        let winners = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..16)
+                .map(|t| {
+                    let map = &map;
+                    s.spawn(move || map.insert_if_absent("leader", t).is_none())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .enumerate()
+                .filter_map(|(t, h)| h.join().unwrap().then_some(t))
+                .collect::<Vec<_>>()
+        });
 
-    fn bitor(self, key: &Q) -> Self::Output {
-        self.get_mut(key).unwrap()
+        assert_eq!(winners.len(), 1);
+        assert_eq!(*map.get("leader").unwrap(), winners[0]);
+        assert_eq!(map.insert_if_absent("leader", 99), Some(99));
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> Sub<&Q> for &'a DashMap<K, V, S>
-where
-    Q: Hash + Equivalent<K> + ?Sized,
-{
-    type Output = Option<(K, V)>;
+    #[test]
+    fn test_contains_all_any() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..32 {
+            map.insert(i, ());
+        }
 
-    fn sub(self, key: &Q) -> Self::Output {
-        self.remove(key)
+        assert!(map.contains_all(core::iter::empty::<&i32>()));
+        assert!(!map.contains_any(core::iter::empty::<&i32>()));
+        assert!(map.contains_all([0, 5, 31].iter()));
+        assert!(!map.contains_all([0, 32, 31].iter()));
+        assert!(map.contains_any([-1, 32, 31].iter()));
+        assert!(!map.contains_any([-1, 32].iter()));
+
+        // Shards are visited in index order, so a write-locked last shard is never reached
+        // when the answer is already known from an earlier one.
+        let shard_of = |key: &i32| map.determine_shard(map.hash_u64(key) as usize);
+        let first = (0..32).find(|k| shard_of(k) == 0).unwrap();
+        let absent = (32..).find(|k| shard_of(k) == 0).unwrap();
+        let last = (0..32).find(|k| shard_of(k) == 3).unwrap();
+
+        let _locked = map.get_mut(&last).unwrap();
+        assert!(map.contains_any([last, first].iter()));
+        assert!(!map.contains_all([last, absent].iter()));
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> BitAnd<&Q> for &'a DashMap<K, V, S>
-where
-    Q: Hash + Equivalent<K> + ?Sized,
-{
-    type Output = bool;
+    #[test]
+    fn test_get_with() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Barrier;
+
+        let map = DashMap::new();
+        let calls = AtomicUsize::new(0);
+        let barrier = Barrier::new(32);
+
+        std::thread::scope(|s| {
+            for _ in 0..32 {
+                s.spawn(|| {
+                    barrier.wait();
+                    let r = map.get_with("key", || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        std::thread::sleep(std::time::Duration::from_millis(50));
+                        String::from("value")
+                    });
+                    assert_eq!(*r, "value");
+                });
+            }
+        });
 
-    fn bitand(self, key: &Q) -> Self::Output {
-        self.contains_key(key)
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(map.len(), 1);
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a + PartialEq, S: BuildHasher + Clone> PartialEq
-    for DashMap<K, V, S>
-{
-    fn eq(&self, other: &Self) -> bool {
-        self.len() == other.len()
-            && self.iter().all(|r| {
-                other
-                    .get(r.key())
-                    .map_or(false, |ro| r.value() == ro.value())
-            })
+    #[test]
+    fn test_try_get_with() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Barrier;
+
+        let map = DashMap::new();
+        let calls = AtomicUsize::new(0);
+        let barrier = Barrier::new(32);
+
+        std::thread::scope(|s| {
+            for _ in 0..32 {
+                s.spawn(|| {
+                    barrier.wait();
+                    let res = map.try_get_with(1, || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        std::thread::sleep(std::time::Duration::from_millis(50));
+                        Err::<u32, _>("unavailable")
+                    });
+                    assert_eq!(res.err().as_deref(), Some(&"unavailable"));
+                });
+            }
+        });
+
+        // The error is shared with the waiters instead of each of them retrying.
+        assert!(calls.load(Ordering::SeqCst) < 32);
+        assert!(map.is_empty());
+
+        assert_eq!(*map.try_get_with(1, || Ok::<_, ()>(7)).unwrap(), 7);
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a + Eq, S: BuildHasher + Clone> Eq for DashMap<K, V, S> {}
+    #[test]
+    fn test_try_get_with_colliding_hashes() {
+        use std::hash::{BuildHasherDefault, Hasher};
+        use std::sync::mpsc;
+        use std::time::Duration;
 
-impl<K: Eq + Hash, V, S: BuildHasher + Clone> IntoIterator for DashMap<K, V, S> {
-    type Item = (K, V);
+        #[derive(Default)]
+        struct Collide;
 
-    type IntoIter = OwningIter<K, V>;
+        impl Hasher for Collide {
+            fn finish(&self) -> u64 {
+                0
+            }
 
-    fn into_iter(self) -> Self::IntoIter {
-        OwningIter::new(self)
+            fn write(&mut self, _bytes: &[u8]) {}
+        }
+
+        let map: DashMap<u32, u32, BuildHasherDefault<Collide>> = DashMap::default();
+        let (started_tx, started_rx) = mpsc::channel();
+        let (done_tx, done_rx) = mpsc::channel();
+
+        std::thread::scope(|s| {
+            let map = &map;
+            s.spawn(move || {
+                let res = map.try_get_with(1, || {
+                    started_tx.send(()).unwrap();
+                    // Only fails if the other key's load waits for this one.
+                    done_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+                    Err::<u32, _>("unavailable")
+                });
+                assert!(res.is_err());
+            });
+
+            started_rx.recv().unwrap();
+            // Same hash, different key: neither waits for nor shares the error of the load above.
+            assert_eq!(*map.try_get_with(2, || Ok::<_, &str>(2)).unwrap(), 2);
+            done_tx.send(()).unwrap();
+        });
+
+        assert!(!map.contains_key(&1));
     }
-}
 
-impl<'a, K: Eq + Hash, V, S: BuildHasher + Clone> IntoIterator for &'a DashMap<K, V, S> {
-    type Item = RefMulti<'a, K, V>;
+    #[test]
+    fn test_get_with_panic() {
+        let map = DashMap::new();
 
-    type IntoIter = Iter<'a, K, V>;
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map.get_with(1, || panic!("loader failed"));
+        }));
+        assert!(res.is_err());
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
+        // The failed load does not keep later callers waiting.
+        assert_eq!(*map.get_with(1, || 2), 2);
     }
-}
 
-impl<K: Eq + Hash, V, S: BuildHasher + Clone> Extend<(K, V)> for DashMap<K, V, S> {
-    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, intoiter: I) {
-        for pair in intoiter.into_iter() {
-            self.insert(pair.0, pair.1);
+    #[test]
+    // The lint cannot see that the clone itself is what is under test.
+    #[allow(clippy::redundant_clone)]
+    fn test_clone() {
+        let map = DashMap::with_capacity_and_hasher_and_shard_amount(1024, RandomState::new(), 8);
+        for i in 0..100 {
+            map.insert(i, i * 2);
         }
+
+        let cloned = map.clone();
+        assert_eq!(cloned, map);
+        assert_eq!(cloned.shard_count(), 8);
+        assert_eq!(cloned.shard_lengths(), map.shard_lengths());
+        assert!(cloned.capacity() >= 1024);
+        assert_eq!(cloned.hash_key(&7), map.hash_key(&7));
+
+        cloned.insert(100, 0);
+        assert_ne!(cloned, map);
     }
-}
 
-impl<K: Eq + Hash, V, S: BuildHasher + Clone + Default> FromIterator<(K, V)> for DashMap<K, V, S> {
-    fn from_iter<I: IntoIterator<Item = (K, V)>>(intoiter: I) -> Self {
-        let mut map = DashMap::default();
+    #[test]
+    fn test_diff() {
+        use crate::Difference;
+
+        let ours = DashMap::with_shard_amount(4);
+        let theirs = DashMap::with_shard_amount(8);
+        for i in 0..100 {
+            ours.insert(i, i);
+            theirs.insert(i + 10, if i % 10 == 0 { 0 } else { i + 10 });
+        }
 
-        map.extend(intoiter);
+        let diff = ours.diff(&theirs);
+        assert_eq!(diff.only_in_self.len(), 10);
+        assert!(diff.only_in_self.iter().all(|&(k, v)| k < 10 && k == v));
+        assert_eq!(diff.only_in_other.len(), 10);
+        assert!(diff.only_in_other.iter().all(|&(k, _)| k >= 100));
+        assert_eq!(diff.changed.len(), 9);
+        assert!(diff.changed.contains(&(20, 20, 0)));
+        assert!(!ours.is_identical_to(&theirs));
+
+        let len = diff.len();
+        let mut iter = diff.clone().into_iter();
+        assert_eq!(iter.len(), len);
+        iter.next();
+        assert_eq!(iter.len(), len - 1);
+        let differences: Vec<_> = diff.into_iter().collect();
+        assert_eq!(differences.len(), len);
+        assert!(differences.contains(&Difference::Changed {
+            key: 50,
+            ours: 50,
+            theirs: 0
+        }));
+
+        let same: DashMap<_, _> = (0..100).map(|i| (i, i)).collect();
+        assert!(ours.diff(&same).is_empty());
+        assert!(ours.is_identical_to(&same));
+    }
 
-        map
+    #[test]
+    fn test_hashmap_round_trip() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..100 {
+            map.insert(i, i.to_string());
+        }
+
+        let std_map = map.to_hashmap();
+        assert_eq!(std_map.len(), 100);
+        assert!(std_map.capacity() >= 100);
+        assert_eq!(std_map[&42], "42");
+
+        let back = DashMap::from(std_map);
+        assert_eq!(back, map);
     }
-}
 
-#[cfg(feature = "typesize")]
-impl<K, V, S> typesize::TypeSize for DashMap<K, V, S>
-where
-    K: typesize::TypeSize + Eq + Hash,
-    V: typesize::TypeSize,
-    S: typesize::TypeSize + Clone + BuildHasher,
-{
-    fn extra_size(&self) -> usize {
-        let shards_extra_size: usize = self
-            .shards
+    #[test]
+    fn test_split_off() {
+        let map = DashMap::with_shard_amount(8);
+        for i in 0..100 {
+            map.insert(i, i.to_string());
+        }
+
+        let odd = map.split_off(|k, _| k % 2 == 1);
+        assert_eq!(odd.shard_count(), 8);
+        assert_eq!((map.len(), odd.len()), (50, 50));
+        assert!(odd
             .iter()
-            .map(|shard_lock| {
-                core::mem::size_of::<CachePadded<RwLock<HashMap<K, V>>>>()
-                    + shard_lock.read().extra_size()
-            })
-            .sum();
+            .all(|r| r.key() % 2 == 1 && *r.value() == r.key().to_string()));
 
-        self.hasher.extra_size() + shards_extra_size
+        map.insert(1, String::from("one"));
+        odd.split_off_into(|k, _| *k < 10, &map);
+        assert_eq!((map.len(), odd.len()), (55, 45));
+        assert_eq!(*map.get(&1).unwrap(), "1");
     }
 
-    typesize::if_typesize_details! {
-        fn get_collection_item_count(&self) -> Option<usize> {
-            Some(self.len())
+    #[test]
+    fn test_fold() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 1..=100 {
+            map.insert(i, i * 2);
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::DashMap;
-    use std::collections::hash_map::RandomState;
+        assert_eq!(map.fold(0, |acc, k, v| acc + k + v), 3 * 5050);
+        assert_eq!(
+            map.fold(Vec::new(), |mut acc, k, _| {
+                acc.push(*k);
+                acc
+            })
+            .len(),
+            100
+        );
+        assert_eq!(map.sum_values::<i32>(), 10100);
+        assert_eq!(DashMap::<i32, i32>::new().sum_values::<i32>(), 0);
+    }
 
     #[test]
-    fn test_basic() {
-        let dm = DashMap::new();
+    fn test_get_timeout() {
+        use std::time::{Duration, Instant};
+
+        let map = DashMap::new();
+        map.insert(1, 1);
+
+        let held = map.get_mut(&1).unwrap();
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let start = Instant::now();
+                assert!(map.get_timeout(&1, Duration::from_millis(20)).is_locked());
+                assert!(map
+                    .get_mut_timeout(&1, Duration::from_millis(20))
+                    .is_locked());
+                assert!(start.elapsed() >= Duration::from_millis(40));
+            });
+        });
+        drop(held);
+
+        // A lock released while waiting is picked up before the timeout.
+        let held = map.get(&1).unwrap();
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let r = map.get_mut_timeout(&1, Duration::from_secs(10));
+                assert_eq!(*r.unwrap(), 1);
+            });
+            std::thread::sleep(Duration::from_millis(20));
+            drop(held);
+        });
 
-        dm.insert(0, 0);
+        assert!(map.get_timeout(&2, Duration::ZERO).is_absent());
+    }
 
-        assert_eq!(dm.get(&0).unwrap().value(), &0);
+    #[test]
+    fn test_get_timeout_with_queued_writer() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let map = DashMap::new();
+        map.insert(1, 1);
+        let (tx, rx) = mpsc::channel();
+
+        let held = map.get_mut(&1).unwrap();
+        std::thread::scope(|s| {
+            let map = &map;
+            s.spawn(move || assert!(map.get_timeout(&1, Duration::from_millis(20)).is_locked()))
+                .join()
+                .unwrap();
+            s.spawn(move || {
+                *map.get_mut(&1).unwrap() += 1;
+                tx.send(()).unwrap();
+            });
+            std::thread::sleep(Duration::from_millis(20));
+            drop(held);
+
+            // The writer must not keep waiting for the reader that gave up.
+            assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        });
+        assert_eq!(*map.get(&1).unwrap(), 2);
     }
 
     #[test]
-    fn test_default() {
-        let dm: DashMap<u32, u32> = DashMap::default();
+    fn test_take_all() {
+        let map = DashMap::with_shard_amount(8);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
 
-        dm.insert(0, 0);
+        let taken = map.take_all();
+        assert!(map.is_empty());
+        assert_eq!(taken.len(), 100);
+        assert_eq!(taken.shard_count(), 8);
+        assert!((0..100).all(|i| *taken.get(&i).unwrap() == i));
 
-        assert_eq!(dm.get(&0).unwrap().value(), &0);
+        map.insert(0, 1);
+        taken.insert(100, 100);
+        assert_eq!((map.len(), taken.len()), (1, 101));
     }
 
     #[test]
-    fn test_equal() {
-        let dm1 = DashMap::new();
-        let dm2 = DashMap::new();
-        assert_eq!(dm1, dm2);
+    fn test_clear_with() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
 
-        dm1.insert(0, "Hello, world!");
-        assert_ne!(dm1, dm2);
+        let mut sum = 0;
+        map.clear_with(|k, v| {
+            // The shard being cleared is already unlocked.
+            assert!(map.try_get(&k).is_absent());
+            sum += v;
+        });
+        assert_eq!(sum, 4950);
+        assert!(map.is_empty());
 
-        dm1.insert(1, "Goodbye, world!");
-        assert_ne!(dm1, dm2);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map.clear_with(|_, _| panic!("teardown failed"));
+        }));
+        assert!(res.is_err());
+        assert!(map.shards.iter().all(|shard| shard.try_write().is_some()));
+        assert!(!map.is_empty());
+    }
 
-        dm2.insert(0, "Hello, world!");
-        assert_ne!(dm1, dm2);
+    #[test]
+    fn test_retain_collect() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
 
-        dm2.insert(1, "Goodbye, world!");
-        assert_eq!(dm1, dm2);
+        let mut removed = map.retain_collect(|k, v| {
+            *v += 1;
+            k % 3 != 0
+        });
+        removed.sort_unstable();
+        assert!(removed
+            .into_iter()
+            .eq((0..100).step_by(3).map(|k| (k, k + 1))));
+        assert_eq!(map.len(), 66);
+        assert!(map.iter().all(|r| *r.value() == r.key() + 1));
+
+        assert_eq!(map.retain_count(|k, _| k % 2 == 0), 33);
+        assert_eq!(map.retain_count(|_, _| true), 0);
+        assert_eq!(map.len(), 33);
     }
 
     #[test]
-    fn test_multiple_hashes() {
-        let dm: DashMap<u32, u32> = DashMap::default();
+    fn test_retain_and_shrink() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..4096 {
+            map.insert(i, i);
+        }
+        let full = map.capacity();
+
+        // Removing half of the entries is not enough to shrink anything.
+        map.retain_and_shrink(|k, _| k % 2 == 0);
+        assert_eq!(map.len(), 2048);
+        // `retain` leaves tombstones behind, which take away from the capacity without a shrink.
+        assert!(map.capacity() > full / 2);
+
+        map.retain_and_shrink(|k, _| *k < 64);
+        assert_eq!(map.len(), 32);
+        assert!(map.capacity() < full / 16);
+        assert!(map.iter().all(|r| *r.key() < 64 && r.key() % 2 == 0));
+    }
 
+    #[test]
+    fn test_extract_if() {
+        let map = DashMap::with_shard_amount(4);
         for i in 0..100 {
-            dm.insert(0, i);
-
-            dm.insert(i, i);
+            map.insert(i, i);
         }
 
-        for i in 1..100 {
-            let r = dm.get(&i).unwrap();
+        let mut evens: Vec<_> = map
+            .extract_if(|_, v| *v % 2 == 0)
+            .map(|(k, _v)| k)
+            .collect();
+        evens.sort_unstable();
+        assert!(evens.into_iter().eq((0..100).step_by(2)));
+        assert_eq!(map.len(), 50);
+
+        // Only the shard holding the first extracted entry is drained.
+        let mut extract = map.extract_if(|_, _| true);
+        let (first, _) = extract.next().unwrap();
+        drop(extract);
+
+        let shard_of = |key: &i32| map.determine_shard(map.hash_u64(key) as usize);
+        let remaining = (0..100).filter(|k| k % 2 == 1 && shard_of(k) != shard_of(&first));
+        assert_eq!(map.len(), remaining.count());
+    }
 
-            assert_eq!(i, *r.value());
+    #[test]
+    fn test_extract_if_mutates_kept() {
+        let sessions = DashMap::new();
+        for id in 0..20 {
+            sessions.insert(id, 0);
+        }
 
-            assert_eq!(i, *r.key());
+        // Every session ages by one tick, and those reaching the limit are handed out.
+        for tick in 1..=3 {
+            let expired: Vec<_> = sessions
+                .extract_if(|id, age| {
+                    *age += 1;
+                    *age >= 3 && id % 2 == 0
+                })
+                .collect();
+            assert_eq!(expired.len(), if tick == 3 { 10 } else { 0 });
+            assert!(expired.iter().all(|&(id, age)| id % 2 == 0 && age == 3));
         }
 
-        let r = dm.get(&0).unwrap();
+        assert_eq!(sessions.len(), 10);
+        assert!(sessions.iter().all(|r| r.key() % 2 == 1 && *r.value() == 3));
+    }
 
-        assert_eq!(99, *r.value());
+    #[test]
+    fn test_get_many_mut() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..64 {
+            map.insert(i, i);
+        }
+
+        let shard_of = |key: &i32| map.determine_shard(map.hash_u64(key) as usize);
+        let same = (1..64).find(|k| shard_of(k) == shard_of(&0)).unwrap();
+        let other = (1..64).find(|k| shard_of(k) != shard_of(&0)).unwrap();
+
+        let [mut a, mut b, mut c] = map.get_many_mut([&other, &0, &same]).unwrap();
+        assert_eq!((*a, *b, *c), (other, 0, same));
+        *a += 100;
+        *b += 100;
+        *c += 100;
+        assert!(map.try_get(&0).is_locked());
+        assert!(map.try_get(&other).is_locked());
+        drop((a, b, c));
+
+        assert_eq!(*map.get(&0).unwrap(), 100);
+        assert_eq!(*map.get(&same).unwrap(), same + 100);
+        assert_eq!(*map.get(&other).unwrap(), other + 100);
+
+        assert!(map.get_many_mut([&0, &same, &0]).is_none());
+        assert!(map.get_many_mut([&0, &64]).is_none());
+        assert!(map.try_get_mut(&0).is_present());
+        assert_eq!(
+            map.get_many_mut::<i32, 0>([]).map(|refs| refs.len()),
+            Some(0)
+        );
     }
 
     #[test]
-    fn test_more_complex_values() {
-        #[derive(Hash, PartialEq, Debug, Clone)]
+    fn test_get_many() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..64 {
+            map.insert(i, i * 10);
+        }
 
-        struct T0 {
-            s: String,
-            u: u8,
+        let [a, b, c] = map.get_many([&5, &64, &5]);
+        assert_eq!(
+            (a.as_deref(), b.as_deref(), c.as_deref()),
+            (Some(&50), None, Some(&50))
+        );
+        assert!(map.try_get_mut(&5).is_locked());
+        drop((a, c));
+
+        assert_eq!(
+            map.get_many_cloned([&1, &2, &-1]),
+            [Some(10), Some(20), None]
+        );
+        assert!(map.try_get_mut(&1).is_present());
+    }
+
+    #[test]
+    fn test_multi_get() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..64 {
+            map.insert(i, i * 10);
         }
 
-        let dm = DashMap::new();
+        let keys = [63, 100, 0, 17, 17, -1, 42];
+        let values: Vec<_> = map
+            .multi_get(keys.iter())
+            .map(|r| r.map(|r| *r.value()))
+            .collect();
+        assert_eq!(
+            values,
+            [
+                Some(630),
+                None,
+                Some(0),
+                Some(170),
+                Some(170),
+                None,
+                Some(420)
+            ]
+        );
+
+        assert_eq!(map.multi_get(core::iter::empty::<&i32>()).count(), 0);
+    }
 
-        let range = 0..10;
+    #[test]
+    fn test_fetch_update() {
+        let map = DashMap::new();
+        map.insert("ceiling", 0);
+
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        let _ = map.fetch_update("ceiling", |n| (*n < 500).then(|| n + 1));
+                    }
+                });
+            }
+        });
+        assert_eq!(*map.get("ceiling").unwrap(), 500);
 
-        for i in range {
-            let t = T0 {
-                s: i.to_string(),
-                u: i as u8,
-            };
+        let mut calls = 0;
+        let res = map.fetch_update("missing", |_| {
+            calls += 1;
+            Some(1)
+        });
+        assert_eq!(res, Err(None));
+        assert_eq!(calls, 0);
+
+        assert_eq!(map.fetch_update_or_insert("new", |_| None, || 7), Ok(None));
+        assert_eq!(map.fetch_update_or_insert("new", |_| None, || 0), Err(7));
+        assert_eq!(
+            map.fetch_update_or_insert("new", |n| Some(n * 2), || 0),
+            Ok(Some(7))
+        );
+        assert_eq!(*map.get("new").unwrap(), 14);
+    }
 
-            dm.insert(i, t.clone());
+    #[test]
+    fn test_compare_exchange() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        const TRANSITIONS: usize = 1000;
+
+        let map = DashMap::new();
+        map.insert("state", 0);
+        let wins: Vec<AtomicUsize> = (0..TRANSITIONS).map(|_| AtomicUsize::new(0)).collect();
+
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    let mut current = 0;
+                    while current < TRANSITIONS {
+                        match map.compare_exchange("state", &current, current + 1) {
+                            Ok(old) => {
+                                wins[old].fetch_add(1, Ordering::Relaxed);
+                                current = old + 1;
+                            }
+                            Err(actual) => current = actual.unwrap(),
+                        }
+                    }
+                });
+            }
+        });
 
-            assert_eq!(&t, dm.get(&i).unwrap().value());
-        }
+        assert!(wins.iter().all(|w| w.load(Ordering::Relaxed) == 1));
+        assert_eq!(*map.get("state").unwrap(), TRANSITIONS);
+        assert_eq!(map.compare_and_swap("missing", &0, 1), Err(None));
     }
 
     #[test]
-    fn test_different_hashers_randomstate() {
-        let dm_hm_default: DashMap<u32, u32, RandomState> =
-            DashMap::with_hasher(RandomState::new());
+    fn test_upsert() {
+        let map = DashMap::new();
 
-        for i in 0..10 {
-            dm_hm_default.insert(i, i);
+        assert_eq!(map.upsert("Johnny", 21), Upsert::Inserted);
+        assert_eq!(map.upsert("Johnny", 22), Upsert::Updated { old: 21 });
 
-            assert_eq!(i, *dm_hm_default.get(&i).unwrap().value());
-        }
+        assert!(map.upsert_with("Johnny", || unreachable!(), |v| *v += 1));
+        assert!(!map.upsert_with("Alice", || 30, |_| unreachable!()));
+
+        assert_eq!(*map.get("Johnny").unwrap(), 23);
+        assert_eq!(*map.get("Alice").unwrap(), 30);
     }
 
     #[test]
-    fn test_map_view() {
-        let dm = DashMap::new();
-
-        let vegetables: [String; 4] = [
-            "Salad".to_string(),
-            "Beans".to_string(),
-            "Potato".to_string(),
-            "Tomato".to_string(),
-        ];
+    fn test_get_or_insert() {
+        let map = DashMap::new();
+
+        assert_eq!(*map.get_or_insert("Johnny", 21), 21);
+        assert_eq!(*map.get_or_insert("Johnny", 42), 21);
+        assert_eq!(map.len(), 1);
+
+        let r = map.get_or_insert("Johnny", 42);
+        assert!(map.try_get("Johnny").is_present());
+        assert!(map.try_get_mut("Johnny").is_locked());
+        drop(r);
+
+        *map.get_mut_or_insert("Johnny", 42) += 1;
+        *map.get_mut_or_insert("Alice", 30) += 1;
+        assert_eq!(*map.get("Johnny").unwrap(), 22);
+        assert_eq!(*map.get("Alice").unwrap(), 31);
+    }
 
-        // Give it some values
-        dm.insert(0, "Banana".to_string());
-        dm.insert(4, "Pear".to_string());
-        dm.insert(9, "Potato".to_string());
-        dm.insert(12, "Chicken".to_string());
+    #[test]
+    fn test_get_or_insert_with() {
+        let map = DashMap::new();
+        let mut calls = 0;
+
+        for _ in 0..3 {
+            let r = map.get_or_insert_with("Johnny", || {
+                calls += 1;
+                20
+            });
+            assert_eq!(*r, 20);
+        }
 
-        let potato_vegetableness = dm.view(&9, |_, v| vegetables.contains(v));
-        assert_eq!(potato_vegetableness, Some(true));
+        assert_eq!(calls, 1);
+    }
 
-        let chicken_vegetableness = dm.view(&12, |_, v| vegetables.contains(v));
-        assert_eq!(chicken_vegetableness, Some(false));
+    #[test]
+    fn test_get_or_insert_with_race() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let map = DashMap::new();
+        let calls = AtomicUsize::new(0);
+
+        std::thread::scope(|s| {
+            for _ in 0..16 {
+                s.spawn(|| {
+                    for key in 0..100 {
+                        let r = map.get_or_insert_with(key, || {
+                            calls.fetch_add(1, Ordering::Relaxed);
+                            key * 2
+                        });
+                        assert_eq!(*r, key * 2);
+                    }
+                });
+            }
+        });
 
-        let not_in_map = dm.view(&30, |_k, _v| false);
-        assert_eq!(not_in_map, None);
+        assert_eq!(calls.load(Ordering::Relaxed), 100);
+        assert_eq!(map.len(), 100);
     }
 
     #[test]
-    fn test_try_get() {
-        {
-            let map = DashMap::new();
-            map.insert("Johnny", 21);
+    fn test_get_mut_or_insert_with() {
+        #[derive(Hash, PartialEq, Eq)]
+        struct NotClone(u32);
 
-            assert_eq!(*map.try_get("Johnny").unwrap(), 21);
+        let map = DashMap::new();
 
-            let _result1_locking = map.get_mut("Johnny");
+        map.get_mut_or_insert_with(NotClone(1), Vec::new).push(1);
+        map.get_mut_or_insert_with(NotClone(1), || unreachable!())
+            .push(2);
+        *map.get_mut_or_default(NotClone(2)) = vec![3];
+        map.get_mut_or_default(NotClone(2)).push(4);
 
-            let result2 = map.try_get("Johnny");
-            assert!(result2.is_locked());
-        }
+        assert_eq!(*map.get(&NotClone(1)).unwrap(), [1, 2]);
+        assert_eq!(*map.get(&NotClone(2)).unwrap(), [3, 4]);
+    }
 
-        {
-            let map = DashMap::new();
-            map.insert("Johnny", 21);
+    #[test]
+    fn test_get_mut_or_default() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
 
-            *map.try_get_mut("Johnny").unwrap() += 1;
-            assert_eq!(*map.get("Johnny").unwrap(), 22);
+        static DEFAULTS: AtomicUsize = AtomicUsize::new(0);
 
-            let _result1_locking = map.get("Johnny");
+        struct Group(Vec<u32>);
 
-            let result2 = map.try_get_mut("Johnny");
-            assert!(result2.is_locked());
+        impl Default for Group {
+            fn default() -> Self {
+                DEFAULTS.fetch_add(1, Ordering::Relaxed);
+                Group(Vec::new())
+            }
         }
+
+        let groups: DashMap<u32, Group> = DashMap::new();
+        for i in 0..10 {
+            groups.get_mut_or_default(i % 3).0.push(i);
+        }
+
+        assert_eq!(DEFAULTS.load(Ordering::Relaxed), 3);
+        assert_eq!(groups.get(&0).unwrap().0, [0, 3, 6, 9]);
+        assert_eq!(groups.get(&1).unwrap().0, [1, 4, 7]);
+        assert_eq!(groups.get(&2).unwrap().0, [2, 5, 8]);
     }
 
     #[test]
@@ -0,0 +1,154 @@
+//! Bookkeeping for [`DashMap::get_with`](crate::DashMap::get_with), which lets only one caller
+//! at a time run the initializer for a missing key while the others wait for it.
+
+use core::hash::Hash;
+use hashbrown::hash_table::HashTable;
+use std::any::Any;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+/// The error a failed initializer returned, type-erased since every call can use its own error type.
+pub(crate) type LoadError = Arc<dyn Any + Send + Sync>;
+
+/// The loads in progress for the keys of a single map.
+pub(crate) struct Loads<K> {
+    in_flight: Mutex<HashTable<InFlight<K>>>,
+}
+
+/// A load in progress. The leader parks its key here while the initializer runs, so callers
+/// whose keys merely have the same hash can tell that the load is not theirs.
+struct InFlight<K> {
+    hash: u64,
+    /// Taken back by the leader to insert the value. Callers with the same hash wait for the load
+    /// from then on, which is harmless since it has succeeded and they look their key up again.
+    key: Option<K>,
+    load: Arc<Load>,
+}
+
+impl<K> Default for Loads<K> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashTable::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash> Loads<K> {
+    // The table is consistent after every operation, so a panic in `K::eq` while it was locked
+    // leaves nothing to clean up.
+    fn lock(&self) -> MutexGuard<'_, HashTable<InFlight<K>>> {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Joins the load of `key`, or starts one.
+    pub(crate) fn join(&self, hash: u64, key: K) -> Role<'_, K> {
+        let mut in_flight = self.lock();
+
+        let joined = in_flight.find(hash, |e| e.key.as_ref().map_or(true, |k| *k == key));
+        if let Some(entry) = joined {
+            return Role::Waiter(entry.load.clone(), key);
+        }
+
+        let load = Arc::new(Load {
+            outcome: Mutex::new(None),
+            finished: Condvar::new(),
+        });
+        in_flight.insert_unique(
+            hash,
+            InFlight {
+                hash,
+                key: Some(key),
+                load: load.clone(),
+            },
+            |e| e.hash,
+        );
+        Role::Leader(LoadGuard {
+            loads: self,
+            hash,
+            load,
+        })
+    }
+}
+
+pub(crate) struct Load {
+    outcome: Mutex<Option<Option<LoadError>>>,
+    finished: Condvar,
+}
+
+impl Load {
+    /// Blocks until the load has finished, and returns the error if the initializer failed.
+    pub(crate) fn wait(&self) -> Option<LoadError> {
+        let mut outcome = self.outcome.lock().unwrap();
+        loop {
+            match &*outcome {
+                Some(error) => return error.clone(),
+                None => outcome = self.finished.wait(outcome).unwrap(),
+            }
+        }
+    }
+}
+
+/// Held by the caller running the initializer. Dropping it without calling [`finish`](Self::finish)
+/// or [`fail`](Self::fail), for instance because the initializer panicked, wakes the waiting callers
+/// so one of them can retry.
+pub(crate) struct LoadGuard<'a, K: Eq + Hash> {
+    loads: &'a Loads<K>,
+    hash: u64,
+    load: Arc<Load>,
+}
+
+impl<K: Eq + Hash> LoadGuard<'_, K> {
+    /// Calls `f` with the key of the load.
+    pub(crate) fn with_key<R>(&self, f: impl FnOnce(&K) -> R) -> R {
+        let in_flight = self.loads.lock();
+        let entry = in_flight
+            .find(self.hash, |e| Arc::ptr_eq(&e.load, &self.load))
+            .expect("load vanished from the table");
+        f(entry.key.as_ref().unwrap())
+    }
+
+    /// Ends a successful load, calling `publish` with the key to insert the value.
+    /// The waiting callers are woken once it returns.
+    pub(crate) fn finish<R>(self, publish: impl FnOnce(K) -> R) -> R {
+        let key = self
+            .loads
+            .lock()
+            .find_mut(self.hash, |e| Arc::ptr_eq(&e.load, &self.load))
+            .and_then(|e| e.key.take())
+            .expect("load vanished from the table");
+        publish(key)
+    }
+
+    /// Ends a failed load, handing `error` to the waiting callers.
+    pub(crate) fn fail(self, error: LoadError) {
+        self.remove();
+        *self.load.outcome.lock().unwrap() = Some(Some(error));
+    }
+
+    fn remove(&self) {
+        let mut in_flight = self.loads.lock();
+        if let Ok(entry) = in_flight.find_entry(self.hash, |e| Arc::ptr_eq(&e.load, &self.load)) {
+            entry.remove();
+        }
+    }
+}
+
+impl<K: Eq + Hash> Drop for LoadGuard<'_, K> {
+    fn drop(&mut self) {
+        self.remove();
+
+        let mut outcome = self.load.outcome.lock().unwrap();
+        if outcome.is_none() {
+            *outcome = Some(None);
+        }
+        self.load.finished.notify_all();
+    }
+}
+
+pub(crate) enum Role<'a, K: Eq + Hash> {
+    /// No load of the key was in progress, the caller must run the initializer.
+    Leader(LoadGuard<'a, K>),
+    /// Another caller is running the initializer for the key, which is handed back.
+    Waiter(Arc<Load>, K),
+}
@@ -1,5 +1,12 @@
+#[cfg(feature = "async")]
+use core::sync::atomic;
 use core::sync::atomic::{AtomicUsize, Ordering};
-use parking_lot_core::{ParkToken, SpinWait, UnparkToken};
+#[cfg(feature = "async")]
+use core::task::Waker;
+use parking_lot_core::{ParkResult, ParkToken, SpinWait, UnparkToken};
+#[cfg(feature = "async")]
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 pub type RwLock<T> = lock_api::RwLock<RawRwLock, T>;
 pub(crate) type RwLockReadGuardDetached<'a> = crate::util::RwLockReadGuardDetached<'a, RawRwLock>;
@@ -12,12 +19,21 @@ const ONE_WRITER: usize = !(READERS_PARKED | WRITERS_PARKED);
 
 pub struct RawRwLock {
     state: AtomicUsize,
+    /// Tasks waiting in [`poll_lock_exclusive`](RawRwLock::poll_lock_exclusive). They count as
+    /// parked writers, so they are woken whenever a parked writer would be.
+    ///
+    /// A task that stops polling leaves its waker behind until the next wake, which then only
+    /// polls a future that has been dropped, or wakes a task for nothing.
+    #[cfg(feature = "async")]
+    wakers: Mutex<Vec<Waker>>,
 }
 
 unsafe impl lock_api::RawRwLock for RawRwLock {
     #[allow(clippy::declare_interior_mutable_const)]
     const INIT: Self = Self {
         state: AtomicUsize::new(0),
+        #[cfg(feature = "async")]
+        wakers: Mutex::new(Vec::new()),
     };
 
     type GuardMarker = lock_api::GuardSend;
@@ -36,7 +52,7 @@ unsafe impl lock_api::RawRwLock for RawRwLock {
             .compare_exchange_weak(0, ONE_WRITER, Ordering::Acquire, Ordering::Relaxed)
             .is_err()
         {
-            self.lock_exclusive_slow();
+            self.lock_exclusive_slow(None);
         }
     }
 
@@ -59,7 +75,7 @@ unsafe impl lock_api::RawRwLock for RawRwLock {
     #[inline]
     fn lock_shared(&self) {
         if !self.try_lock_shared_fast() {
-            self.lock_shared_slow();
+            self.lock_shared_slow(None);
         }
     }
 
@@ -85,9 +101,143 @@ unsafe impl lock_api::RawRwLockDowngrade for RawRwLock {
     }
 }
 
+unsafe impl lock_api::RawRwLockRecursive for RawRwLock {
+    // Readers never wait on parked writers, so a recursive shared acquisition
+    // is the same operation as a regular one.
+    #[inline]
+    fn lock_shared_recursive(&self) {
+        <Self as lock_api::RawRwLock>::lock_shared(self)
+    }
+
+    #[inline]
+    fn try_lock_shared_recursive(&self) -> bool {
+        <Self as lock_api::RawRwLock>::try_lock_shared(self)
+    }
+}
+
+// The last thread to time out while parked clears its parked bit, so that unlocking does not wake
+// a queue that has emptied instead of the one that is still waiting.
+unsafe impl lock_api::RawRwLockTimed for RawRwLock {
+    type Duration = Duration;
+    type Instant = Instant;
+
+    #[inline]
+    fn try_lock_shared_for(&self, timeout: Duration) -> bool {
+        self.try_lock_shared_fast() || self.lock_shared_slow(Instant::now().checked_add(timeout))
+    }
+
+    #[inline]
+    fn try_lock_shared_until(&self, timeout: Instant) -> bool {
+        self.try_lock_shared_fast() || self.lock_shared_slow(Some(timeout))
+    }
+
+    #[inline]
+    fn try_lock_exclusive_for(&self, timeout: Duration) -> bool {
+        <Self as lock_api::RawRwLock>::try_lock_exclusive(self)
+            || self.lock_exclusive_slow(Instant::now().checked_add(timeout))
+    }
+
+    #[inline]
+    fn try_lock_exclusive_until(&self, timeout: Instant) -> bool {
+        <Self as lock_api::RawRwLock>::try_lock_exclusive(self)
+            || self.lock_exclusive_slow(Some(timeout))
+    }
+}
+
 impl RawRwLock {
+    /// Turns a shared lock held by the caller into an exclusive lock,
+    /// failing if any other shared lock is held.
+    ///
+    /// # Safety
+    ///
+    /// The caller must hold a shared lock.
+    #[inline]
+    pub(crate) unsafe fn try_upgrade(&self) -> bool {
+        let state = self.state.load(Ordering::Relaxed);
+
+        // Parked threads are woken up by `unlock_exclusive` as usual.
+        state & ONE_WRITER == ONE_READER
+            && self
+                .state
+                .compare_exchange(
+                    state,
+                    state | ONE_WRITER,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    /// Tries to acquire an exclusive lock, or arranges for the task to be woken once it is
+    /// released if that fails.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_lock_exclusive(
+        &self,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<()> {
+        use core::task::Poll;
+
+        loop {
+            if <Self as lock_api::RawRwLock>::try_lock_exclusive(self) {
+                return Poll::Ready(());
+            }
+
+            {
+                let mut wakers = self.wakers.lock().unwrap();
+                if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+            }
+
+            // The waker must be registered before the parked bit is set, since whoever
+            // sees the bit when unlocking takes the wakers.
+            let mut state = self.state.load(Ordering::Relaxed);
+            loop {
+                if state & ONE_WRITER == 0 {
+                    // Released in the meantime, try again.
+                    break;
+                }
+                if state & WRITERS_PARKED != 0 {
+                    return Poll::Pending;
+                }
+                match self.state.compare_exchange_weak(
+                    state,
+                    state | WRITERS_PARKED,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return Poll::Pending,
+                    Err(e) => state = e,
+                }
+            }
+        }
+    }
+
+    /// Clears the parked bit of the writers once the last parked writer stopped waiting,
+    /// unless tasks are still waiting for the lock.
+    fn clear_writers_parked(&self) {
+        // Holding the wakers keeps a task from registering in between the check and the clear.
+        #[cfg(feature = "async")]
+        let wakers = self.wakers.lock().unwrap();
+        #[cfg(feature = "async")]
+        if !wakers.is_empty() {
+            return;
+        }
+        self.state.fetch_and(!WRITERS_PARKED, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "async")]
+    #[cold]
+    fn wake_tasks(&self) {
+        // Pairs with the release when setting the parked bit, so the registered waker is seen.
+        atomic::fence(Ordering::Acquire);
+        let wakers = core::mem::take(&mut *self.wakers.lock().unwrap());
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Returns false if `timeout` passed before the lock could be acquired.
     #[cold]
-    fn lock_exclusive_slow(&self) {
+    fn lock_exclusive_slow(&self, timeout: Option<Instant>) -> bool {
         let mut acquire_with = 0;
         loop {
             let mut spin = SpinWait::new();
@@ -101,7 +251,7 @@ impl RawRwLock {
                         Ordering::Acquire,
                         Ordering::Relaxed,
                     ) {
-                        Ok(_) => return,
+                        Ok(_) => return true,
                         Err(e) => state = e,
                     }
                 }
@@ -123,7 +273,7 @@ impl RawRwLock {
                     }
                 }
 
-                let _ = unsafe {
+                let result = unsafe {
                     parking_lot_core::park(
                         self as *const _ as usize,
                         || {
@@ -131,12 +281,20 @@ impl RawRwLock {
                             (state & ONE_WRITER != 0) && (state & WRITERS_PARKED != 0)
                         },
                         || {},
-                        |_, _| {},
+                        |_, was_last_thread| {
+                            if was_last_thread {
+                                self.clear_writers_parked();
+                            }
+                        },
                         ParkToken(0),
-                        None,
+                        timeout,
                     )
                 };
 
+                if result == ParkResult::TimedOut {
+                    return false;
+                }
+
                 acquire_with = WRITERS_PARKED;
                 break;
             }
@@ -163,7 +321,26 @@ impl RawRwLock {
 
         if parked == (READERS_PARKED | WRITERS_PARKED) {
             self.state.store(WRITERS_PARKED, Ordering::Release);
-            parked = READERS_PARKED;
+
+            let woken = unsafe {
+                parking_lot_core::unpark_all((self as *const _ as usize) + 1, UnparkToken(0))
+            };
+            if woken > 0 {
+                // The last of the woken readers to unlock wakes the writers.
+                return;
+            }
+
+            // The reader bit was left behind by a reader that stopped waiting, for instance because
+            // the lock was released before it parked. Wake the writers here, or nobody would.
+            if self
+                .state
+                .compare_exchange(WRITERS_PARKED, 0, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+            {
+                // Someone took the lock in the meantime, and wakes the writers when unlocking it.
+                return;
+            }
+            parked = WRITERS_PARKED;
         }
 
         if parked == READERS_PARKED {
@@ -176,6 +353,8 @@ impl RawRwLock {
         unsafe {
             parking_lot_core::unpark_one(self as *const _ as usize, |_| UnparkToken(0));
         }
+        #[cfg(feature = "async")]
+        self.wake_tasks();
     }
 
     #[inline(always)]
@@ -217,8 +396,9 @@ impl RawRwLock {
         false
     }
 
+    /// Returns false if `timeout` passed before the lock could be acquired.
     #[cold]
-    fn lock_shared_slow(&self) {
+    fn lock_shared_slow(&self, timeout: Option<Instant>) -> bool {
         loop {
             let mut spin = SpinWait::new();
             let mut state = self.state.load(Ordering::Relaxed);
@@ -242,7 +422,7 @@ impl RawRwLock {
                         )
                         .is_ok()
                     {
-                        return;
+                        return true;
                     }
 
                     backoff.spin_no_yield();
@@ -266,7 +446,7 @@ impl RawRwLock {
                     }
                 }
 
-                let _ = unsafe {
+                let result = unsafe {
                     parking_lot_core::park(
                         (self as *const _ as usize) + 1,
                         || {
@@ -274,12 +454,20 @@ impl RawRwLock {
                             (state & ONE_WRITER == ONE_WRITER) && (state & READERS_PARKED != 0)
                         },
                         || {},
-                        |_, _| {},
+                        |_, was_last_thread| {
+                            if was_last_thread {
+                                self.state.fetch_and(!READERS_PARKED, Ordering::Relaxed);
+                            }
+                        },
                         ParkToken(0),
-                        None,
+                        timeout,
                     )
                 };
 
+                if result == ParkResult::TimedOut {
+                    return false;
+                }
+
                 break;
             }
         }
@@ -295,6 +483,44 @@ impl RawRwLock {
             unsafe {
                 parking_lot_core::unpark_one(self as *const _ as usize, |_| UnparkToken(0));
             }
+            #[cfg(feature = "async")]
+            self.wake_tasks();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RwLock;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn timed_out_reader_does_not_strand_writer() {
+        let lock = RwLock::new(());
+        let (tx, rx) = mpsc::channel();
+
+        thread::scope(|s| {
+            let guard = lock.write();
+            let lock = &lock;
+
+            s.spawn(move || assert!(lock.try_read_for(Duration::from_millis(50)).is_none()))
+                .join()
+                .unwrap();
+
+            s.spawn(move || {
+                let _guard = lock.write();
+                tx.send(()).unwrap();
+            });
+            // Give the writer time to park.
+            thread::sleep(Duration::from_millis(50));
+            drop(guard);
+
+            let woken = rx.recv_timeout(Duration::from_secs(5));
+            // Unstick the writer so the scope can end if it was not woken.
+            drop(lock.read());
+            assert!(woken.is_ok(), "writer was not woken");
+        });
+    }
+}
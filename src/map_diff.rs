@@ -0,0 +1,80 @@
+/// The differences between two maps, see [`DashMap::diff`](crate::DashMap::diff).
+///
+/// Iterating over it yields every difference as a [`Difference`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MapDiff<K, V> {
+    /// Entries whose key is only present in the map `diff` was called on.
+    pub only_in_self: Vec<(K, V)>,
+    /// Entries whose key is only present in the other map.
+    pub only_in_other: Vec<(K, V)>,
+    /// Keys present in both maps with unequal values, followed by the value in the map `diff`
+    /// was called on and the value in the other map.
+    pub changed: Vec<(K, V, V)>,
+}
+
+/// A single difference between two maps, see [`MapDiff`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Difference<K, V> {
+    /// The key is only present in the map `diff` was called on.
+    OnlyInSelf(K, V),
+    /// The key is only present in the other map.
+    OnlyInOther(K, V),
+    /// The key is present in both maps with unequal values.
+    Changed { key: K, ours: V, theirs: V },
+}
+
+impl<K, V> MapDiff<K, V> {
+    /// Returns `true` if the maps had the same contents.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty() && self.only_in_other.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns the number of differences.
+    pub fn len(&self) -> usize {
+        self.only_in_self.len() + self.only_in_other.len() + self.changed.len()
+    }
+}
+
+impl<K, V> IntoIterator for MapDiff<K, V> {
+    type Item = Difference<K, V>;
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            only_in_self: self.only_in_self.into_iter(),
+            only_in_other: self.only_in_other.into_iter(),
+            changed: self.changed.into_iter(),
+        }
+    }
+}
+
+/// Iterator over the differences of a [`MapDiff`]: first the keys only in the map `diff` was called
+/// on, then the keys only in the other map, then the changed keys.
+#[derive(Debug)]
+pub struct IntoIter<K, V> {
+    only_in_self: std::vec::IntoIter<(K, V)>,
+    only_in_other: std::vec::IntoIter<(K, V)>,
+    changed: std::vec::IntoIter<(K, V, V)>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = Difference<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((k, v)) = self.only_in_self.next() {
+            return Some(Difference::OnlyInSelf(k, v));
+        }
+        if let Some((k, v)) = self.only_in_other.next() {
+            return Some(Difference::OnlyInOther(k, v));
+        }
+        let (key, ours, theirs) = self.changed.next()?;
+        Some(Difference::Changed { key, ours, theirs })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.only_in_self.len() + self.only_in_other.len() + self.changed.len();
+        (len, Some(len))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
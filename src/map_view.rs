@@ -0,0 +1,119 @@
+use crate::lock::RwLockReadGuardDetached;
+use crate::{DashMap, HashMap};
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use equivalent::Equivalent;
+use std::collections::hash_map::RandomState;
+
+/// A consistent read-only view of a whole `DashMap`, see [`DashMap::lock_all_read`].
+///
+/// Every shard stays read-locked until the view is dropped, so nothing can change the map meanwhile.
+pub struct MapView<'a, K, V, S = RandomState> {
+    map: &'a DashMap<K, V, S>,
+    shards: Box<[&'a HashMap<K, V>]>,
+    _guards: Box<[RwLockReadGuardDetached<'a>]>,
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher + Clone> MapView<'a, K, V, S> {
+    pub(crate) fn new(map: &'a DashMap<K, V, S>) -> Self {
+        // Shards are always locked in index order, so two threads locking several of them
+        // can not each end up waiting for a shard the other one holds.
+        let (guards, shards) = map
+            .shards
+            .iter()
+            // SAFETY: The data will not outlive the guards, since both are kept in `MapView`.
+            .map(|shard| unsafe { RwLockReadGuardDetached::detach_from(shard.read()) })
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+
+        Self {
+            map,
+            shards: shards.into_boxed_slice(),
+            _guards: guards.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the number of elements in the map.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.len()).sum()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        self.get_key_value(key).map(|(_k, v)| v)
+    }
+
+    /// Returns the key-value pair corresponding to the supplied key.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        Q: Hash + Equivalent<K> + ?Sized,
+    {
+        let hash = self.map.hash_u64(&key);
+
+        let idx = self.map.determine_shard(hash as usize);
+
+        self.shards[idx]
+            .find(hash, |(k, _v)| key.equivalent(k))
+            .map(|(k, v)| (k, v))
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The iterator element type is `(&K, &V)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.shards
+            .iter()
+            .flat_map(|shard| shard.iter())
+            .map(|(k, v)| (k, v))
+    }
+}
+
+impl<'a, K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher + Clone> fmt::Debug
+    for MapView<'a, K, V, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DashMap;
+
+    #[test]
+    fn test_view() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..100 {
+            map.insert(i, i * 2);
+        }
+
+        let view = map.lock_all_read();
+        assert_eq!(view.len(), 100);
+        assert!(!view.is_empty());
+        assert_eq!(view.get(&7), Some(&14));
+        assert!(!view.contains_key(&100));
+        assert_eq!(view.iter().map(|(_k, v)| v).sum::<i32>(), 9900);
+
+        for i in 0..100 {
+            assert!(map.try_get(&i).is_present());
+            assert!(map.try_get_mut(&i).is_locked());
+        }
+        assert!(map.try_entry(100).is_none());
+
+        drop(view);
+        assert!(map.try_entry(100).is_some());
+    }
+}
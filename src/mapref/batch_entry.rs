@@ -0,0 +1,196 @@
+use hashbrown::hash_table;
+
+use core::hash::Hash;
+use core::mem;
+
+/// An entry handed out by [`DashMap::entry_many`](crate::DashMap::entry_many).
+///
+/// Unlike [`Entry`](super::entry::Entry), it holds no lock of its own. It borrows the shard
+/// that `entry_many` keeps locked, so it only gives out plain references.
+pub enum BatchEntry<'a, K, V> {
+    /// The key is present in the map.
+    Occupied(BatchOccupiedEntry<'a, K, V>),
+    /// The key is not present in the map.
+    Vacant(BatchVacantEntry<'a, K, V>),
+}
+
+impl<'a, K: Eq + Hash, V> BatchEntry<'a, K, V> {
+    pub(crate) fn new(key: K, entry: hash_table::Entry<'a, (K, V)>) -> Self {
+        match entry {
+            hash_table::Entry::Occupied(entry) => {
+                BatchEntry::Occupied(BatchOccupiedEntry { key, entry })
+            }
+            hash_table::Entry::Vacant(entry) => BatchEntry::Vacant(BatchVacantEntry { key, entry }),
+        }
+    }
+
+    /// Apply a function to the stored value if it exists.
+    pub fn and_modify(self, f: impl FnOnce(&mut V)) -> Self {
+        match self {
+            BatchEntry::Occupied(mut entry) => {
+                f(entry.get_mut());
+
+                BatchEntry::Occupied(entry)
+            }
+
+            BatchEntry::Vacant(entry) => BatchEntry::Vacant(entry),
+        }
+    }
+
+    /// Get the key of the entry.
+    pub fn key(&self) -> &K {
+        match *self {
+            BatchEntry::Occupied(ref entry) => entry.key(),
+            BatchEntry::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Into the key of the entry.
+    pub fn into_key(self) -> K {
+        match self {
+            BatchEntry::Occupied(entry) => entry.into_key(),
+            BatchEntry::Vacant(entry) => entry.into_key(),
+        }
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise insert the default and return a mutable reference to that.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise a provided value and return a mutable reference to that.
+    pub fn or_insert(self, value: V) -> &'a mut V {
+        match self {
+            BatchEntry::Occupied(entry) => entry.into_mut(),
+            BatchEntry::Vacant(entry) => entry.insert(value),
+        }
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise insert the result of a provided function and return a mutable reference to that.
+    pub fn or_insert_with(self, value: impl FnOnce() -> V) -> &'a mut V {
+        match self {
+            BatchEntry::Occupied(entry) => entry.into_mut(),
+            BatchEntry::Vacant(entry) => entry.insert(value()),
+        }
+    }
+
+    /// Sets the value of the entry, and returns a mutable reference to the inserted value.
+    pub fn insert(self, value: V) -> &'a mut V {
+        match self {
+            BatchEntry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry.into_mut()
+            }
+            BatchEntry::Vacant(entry) => entry.insert(value),
+        }
+    }
+}
+
+/// A [`BatchEntry`] for a key that is not present in the map.
+pub struct BatchVacantEntry<'a, K, V> {
+    key: K,
+    entry: hash_table::VacantEntry<'a, (K, V)>,
+}
+
+impl<'a, K: Eq + Hash, V> BatchVacantEntry<'a, K, V> {
+    /// Inserts the value under the key of the entry, and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        &mut self.entry.insert((self.key, value)).into_mut().1
+    }
+
+    /// Takes back the key that would have been inserted.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Get the key that would be inserted.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+/// A [`BatchEntry`] for a key that is present in the map.
+pub struct BatchOccupiedEntry<'a, K, V> {
+    key: K,
+    entry: hash_table::OccupiedEntry<'a, (K, V)>,
+}
+
+impl<'a, K: Eq + Hash, V> BatchOccupiedEntry<'a, K, V> {
+    /// Get a reference to the stored value.
+    pub fn get(&self) -> &V {
+        &self.entry.get().1
+    }
+
+    /// Get a mutable reference to the stored value.
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.entry.get_mut().1
+    }
+
+    /// Turns the entry into a mutable reference to the stored value, borrowed from the shard.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.entry.into_mut().1
+    }
+
+    /// Replaces the stored value, and returns the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Takes back the key the entry was looked up with, rather than the stored one.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Get the key stored in the map.
+    pub fn key(&self) -> &K {
+        &self.entry.get().0
+    }
+
+    /// Removes the entry from the map, and returns its value.
+    pub fn remove(self) -> V {
+        let ((_k, v), _) = self.entry.remove();
+        v
+    }
+
+    /// Removes the entry from the map, and returns the stored key and the value.
+    pub fn remove_entry(self) -> (K, V) {
+        let ((k, v), _) = self.entry.remove();
+        (k, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_remove_in_batch() {
+        let map = DashMap::with_shard_amount(4);
+        for i in 0..32 {
+            map.insert(i, i);
+        }
+
+        map.entry_many(0..40, |entry| match entry {
+            BatchEntry::Occupied(entry) if entry.get() % 2 == 0 => {
+                entry.remove();
+            }
+            BatchEntry::Occupied(mut entry) => {
+                entry.insert(0);
+            }
+            BatchEntry::Vacant(entry) => {
+                entry.insert(1);
+            }
+        });
+
+        assert_eq!(map.len(), 24);
+        assert!(map.iter().all(|r| *r.value() == (*r.key() >= 32) as i32));
+    }
+}
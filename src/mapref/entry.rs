@@ -1,7 +1,8 @@
 use hashbrown::hash_table;
 
-use super::one::RefMut;
+use super::one::{Ref, RefMut};
 use crate::lock::RwLockWriteGuardDetached;
+use core::fmt::{self, Debug, Formatter};
 use core::hash::Hash;
 use core::mem;
 
@@ -24,6 +25,16 @@ impl<'a, K: Eq + Hash, V> Entry<'a, K, V> {
         }
     }
 
+    /// Replace the stored value with the result of a function if it exists,
+    /// or remove it if the function returns `None`.
+    pub fn and_replace_entry_with(self, f: impl FnOnce(&K, V) -> Option<V>) -> Self {
+        match self {
+            Entry::Occupied(entry) => entry.replace_entry_with(f),
+
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+
     /// Get the key of the entry.
     pub fn key(&self) -> &K {
         match *self {
@@ -70,6 +81,23 @@ impl<'a, K: Eq + Hash, V> Entry<'a, K, V> {
         }
     }
 
+    /// Like [`or_insert`](Entry::or_insert), but downgrades the shard lock and returns a shared reference,
+    /// so other readers of the shard are not blocked while it is held.
+    pub fn or_insert_ref(self, value: V) -> Ref<'a, K, V> {
+        self.or_insert(value).downgrade()
+    }
+
+    /// Like [`or_insert_with`](Entry::or_insert_with), but downgrades the shard lock and returns a shared reference,
+    /// so other readers of the shard are not blocked while it is held.
+    pub fn or_insert_with_ref(self, value: impl FnOnce() -> V) -> Ref<'a, K, V> {
+        self.or_insert_with(value).downgrade()
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise insert the result of a fallible function and return a mutable reference to that.
+    ///
+    /// The shard stays write-locked while `value` runs. If it returns an error or panics,
+    /// nothing is inserted and the shard is unlocked again.
     pub fn or_try_insert_with<E>(
         self,
         value: impl FnOnce() -> Result<V, E>,
@@ -80,6 +108,20 @@ impl<'a, K: Eq + Hash, V> Entry<'a, K, V> {
         }
     }
 
+    /// Like [`or_try_insert_with`](Entry::or_try_insert_with), but `value` is given the key.
+    pub fn or_try_insert_with_key<E>(
+        self,
+        value: impl FnOnce(&K) -> Result<V, E>,
+    ) -> Result<RefMut<'a, K, V>, E> {
+        match self {
+            Entry::Occupied(entry) => Ok(entry.into_ref()),
+            Entry::Vacant(entry) => {
+                let value = value(entry.key())?;
+                Ok(entry.insert(value))
+            }
+        }
+    }
+
     /// Sets the value of the entry, and returns a reference to the inserted value.
     pub fn insert(self, value: V) -> RefMut<'a, K, V> {
         match self {
@@ -206,8 +248,76 @@ impl<'a, K: Eq + Hash, V> OccupiedEntry<'a, K, V> {
         let (k, v) = mem::replace(self.entry.into_mut(), (self.key, value));
         (k, v)
     }
+
+    /// Replaces the key stored in the map with the key used to look up this entry,
+    /// keeping the value, and returns the old key.
+    ///
+    /// This is useful for interning, where the stored key should be swapped for a canonical one.
+    pub fn replace_key(self) -> K {
+        mem::replace(&mut self.entry.into_mut().0, self.key)
+    }
+
+    /// Replaces the value with the result of `f`, or removes the entry if `f` returns `None`.
+    /// The returned entry reflects the new state and keeps the shard locked.
+    pub fn replace_entry_with(self, f: impl FnOnce(&K, V) -> Option<V>) -> Entry<'a, K, V> {
+        let ((k, v), entry) = self.entry.remove();
+        match f(&k, v) {
+            Some(v) => Entry::Occupied(OccupiedEntry::new(
+                self.shard,
+                self.key,
+                entry.insert((k, v)),
+            )),
+            None => Entry::Vacant(VacantEntry::new(self.shard, self.key, entry)),
+        }
+    }
+}
+
+/// The error returned by [`DashMap::try_insert`](crate::DashMap::try_insert) when the key already exists.
+///
+/// Contains the occupied entry, which keeps the shard locked, and the value that was not inserted.
+pub struct OccupiedError<'a, K, V> {
+    /// The entry in the map that was already occupied.
+    pub entry: OccupiedEntry<'a, K, V>,
+    /// The value which was not inserted, because the entry was already occupied.
+    pub value: V,
+}
+
+impl<'a, K, V> OccupiedError<'a, K, V> {
+    /// Returns the entry in the map that was already occupied.
+    pub fn entry(&self) -> &OccupiedEntry<'a, K, V> {
+        &self.entry
+    }
+
+    /// Returns the value which was not inserted.
+    pub fn value(&self) -> &V {
+        &self.value
+    }
 }
 
+impl<'a, K: Eq + Hash + Debug, V: Debug> Debug for OccupiedError<'a, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<'a, K: Eq + Hash + Debug, V: Debug> fmt::Display for OccupiedError<'a, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key(),
+            self.entry.get(),
+        )
+    }
+}
+
+impl<'a, K: Eq + Hash + Debug, V: Debug> std::error::Error for OccupiedError<'a, K, V> {}
+
 #[cfg(test)]
 mod tests {
     use crate::DashMap;
@@ -231,6 +341,50 @@ mod tests {
         assert_eq!(*map.get(&1).unwrap(), 2);
     }
 
+    #[test]
+    fn test_or_insert_ref() {
+        let map: DashMap<u32, u32> = DashMap::new();
+
+        let inserted = map.entry(1).or_insert_with_ref(|| 10);
+        assert_eq!(*inserted, 10);
+
+        std::thread::scope(|s| {
+            s.spawn(|| assert_eq!(*map.get(&1).unwrap(), 10));
+        });
+        assert!(map.try_get_mut(&1).is_locked());
+        drop(inserted);
+
+        assert_eq!(*map.entry(1).or_insert_ref(20), 10);
+        assert_eq!(*map.entry(2).or_insert_ref(20), 20);
+    }
+
+    #[test]
+    fn test_or_try_insert_with_key() {
+        let map: DashMap<u32, u32> = DashMap::new();
+
+        let res: Result<_, ()> = map.entry(1).or_try_insert_with_key(|k| Ok(k * 10));
+        assert_eq!(*res.unwrap(), 10);
+
+        let res: Result<_, ()> = map.entry(1).or_try_insert_with_key(|_| unreachable!());
+        assert_eq!(*res.unwrap(), 10);
+
+        let Err(err) = map.entry(2).or_try_insert_with_key(|k| Err(*k)) else {
+            panic!("value construction failed")
+        };
+        assert_eq!(err, 2);
+        assert!(map.try_get_mut(&2).is_absent());
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _ = map
+                .entry(3)
+                .or_try_insert_with_key(|_| -> Result<u32, ()> { panic!("lookup failed") });
+        }));
+        assert!(panicked.is_err());
+        assert!(map.try_get_mut(&3).is_absent());
+
+        assert_eq!(map.len(), 1);
+    }
+
     #[test]
     fn test_insert_into_occupied() {
         let map: DashMap<u32, u32> = DashMap::new();
@@ -267,6 +421,67 @@ mod tests {
         assert_eq!(*map.get(&1).unwrap(), 2);
     }
 
+    #[test]
+    fn test_vacant_into_key() {
+        let map: DashMap<String, u32> = DashMap::new();
+
+        let key = String::with_capacity(16) + "one";
+
+        let Entry::Vacant(entry) = map.entry(key) else {
+            panic!("map is empty")
+        };
+
+        let key = entry.into_key();
+
+        assert_eq!(key, "one");
+        assert!(key.capacity() >= 16);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_vacant_insert_entry_then_remove() {
+        let map: DashMap<u32, u32> = DashMap::new();
+
+        map.insert(2, 20);
+
+        let Entry::Vacant(entry) = map.entry(1) else {
+            panic!("key 1 is absent")
+        };
+
+        let mut entry = entry.insert_entry(10);
+
+        assert_eq!(*entry.key(), 1);
+        assert_eq!(entry.insert(11), 10);
+        assert_eq!(entry.remove(), 11);
+
+        assert!(map.get(&1).is_none());
+        assert_eq!(*map.get(&2).unwrap(), 20);
+    }
+
+    #[test]
+    fn test_replace_key() {
+        let map: DashMap<String, u32> = DashMap::new();
+
+        let original = String::from("one");
+        let original_ptr = original.as_ptr();
+        map.insert(original, 1);
+
+        let canonical = String::with_capacity(16) + "one";
+
+        let Entry::Occupied(entry) = map.entry(canonical) else {
+            panic!("key is present")
+        };
+
+        let old = entry.replace_key();
+
+        assert_eq!(old, "one");
+        assert_eq!(old.as_ptr(), original_ptr);
+
+        let stored = map.get("one").unwrap();
+        assert_eq!(*stored, 1);
+        assert!(stored.key().capacity() >= 16);
+    }
+
     #[test]
     fn test_insert_entry_into_occupied() {
         let map: DashMap<u32, u32> = DashMap::new();
@@ -285,4 +500,26 @@ mod tests {
 
         assert_eq!(*map.get(&1).unwrap(), 2);
     }
+
+    #[test]
+    fn test_replace_entry_with() {
+        let map: DashMap<u32, u32> = DashMap::new();
+
+        map.insert(1, 1);
+
+        let Entry::Occupied(entry) = map.entry(1) else {
+            panic!("entry exists")
+        };
+        let entry = entry.replace_entry_with(|k, v| Some(k + v));
+        assert!(matches!(&entry, Entry::Occupied(entry) if *entry.get() == 2));
+
+        let entry = entry.and_replace_entry_with(|_, v| if v > 1 { None } else { Some(v) });
+        assert!(matches!(entry, Entry::Vacant(_)));
+        assert!(map.try_get(&1).is_locked());
+
+        let entry = entry.and_replace_entry_with(|_, _| unreachable!());
+        *entry.or_insert(5) += 1;
+
+        assert_eq!(*map.get(&1).unwrap(), 6);
+    }
 }
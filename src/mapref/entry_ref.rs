@@ -316,4 +316,59 @@ mod tests {
 
         assert_eq!(*map.get(&1).unwrap(), 2);
     }
+    /// A borrowed key that counts how often it is turned into an owned one.
+    struct Counted(u32, std::cell::Cell<usize>);
+    impl core::hash::Hash for Counted {
+        fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state)
+        }
+    }
+    impl From<&Counted> for u32 {
+        fn from(value: &Counted) -> Self {
+            value.1.set(value.1.get() + 1);
+            value.0
+        }
+    }
+    impl Equivalent<u32> for Counted {
+        fn equivalent(&self, key: &u32) -> bool {
+            self.0 == *key
+        }
+    }
+
+    #[test]
+    fn test_key_only_converted_when_vacant() {
+        let map: DashMap<u32, u32> = DashMap::new();
+
+        let key = Counted(1, Default::default());
+
+        *map.entry_ref(&key).or_insert(0) += 1;
+
+        assert_eq!(key.1.get(), 1);
+
+        *map.entry_ref(&key).or_insert(0) += 1;
+        map.entry_ref(&key).and_modify(|v| *v += 1).or_default();
+
+        assert_eq!(key.1.get(), 1);
+
+        assert_eq!(*map.get(&1).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_or_insert_with_on_occupied() {
+        let map: DashMap<u32, u32> = DashMap::new();
+        map.insert(1, 10);
+
+        let key = Counted(1, Default::default());
+
+        let value = map.entry_ref(&key).or_insert_with(|| unreachable!());
+        assert_eq!(*value, 10);
+        drop(value);
+
+        assert_eq!(
+            *map.entry_ref(&Counted(2, Default::default()))
+                .or_insert_with(|| 20),
+            20
+        );
+        assert_eq!(key.1.get(), 0);
+    }
 }
@@ -1,4 +1,48 @@
+/// Implements `PartialEq` and `PartialOrd` between a guard and the value it points to,
+/// a reference to that value, and another guard of the same kind.
+macro_rules! impl_cmp {
+    ($guard:ident: $($sized:tt)+) => {
+        impl<K, V: $($sized)+ + PartialEq> PartialEq<V> for $guard<'_, K, V> {
+            fn eq(&self, other: &V) -> bool {
+                **self == *other
+            }
+        }
+
+        impl<K, V: $($sized)+ + PartialEq> PartialEq<&V> for $guard<'_, K, V> {
+            fn eq(&self, other: &&V) -> bool {
+                **self == **other
+            }
+        }
+
+        impl<K, K2, V: $($sized)+ + PartialEq> PartialEq<$guard<'_, K2, V>> for $guard<'_, K, V> {
+            fn eq(&self, other: &$guard<'_, K2, V>) -> bool {
+                **self == **other
+            }
+        }
+
+        impl<K, V: $($sized)+ + PartialOrd> PartialOrd<V> for $guard<'_, K, V> {
+            fn partial_cmp(&self, other: &V) -> Option<core::cmp::Ordering> {
+                (**self).partial_cmp(other)
+            }
+        }
+
+        impl<K, V: $($sized)+ + PartialOrd> PartialOrd<&V> for $guard<'_, K, V> {
+            fn partial_cmp(&self, other: &&V) -> Option<core::cmp::Ordering> {
+                (**self).partial_cmp(*other)
+            }
+        }
+
+        impl<K, K2, V: $($sized)+ + PartialOrd> PartialOrd<$guard<'_, K2, V>> for $guard<'_, K, V> {
+            fn partial_cmp(&self, other: &$guard<'_, K2, V>) -> Option<core::cmp::Ordering> {
+                (**self).partial_cmp(&**other)
+            }
+        }
+    };
+}
+
+pub mod batch_entry;
 pub mod entry;
 pub mod entry_ref;
 pub mod multiple;
 pub mod one;
+pub mod owned;
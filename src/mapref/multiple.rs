@@ -1,18 +1,32 @@
+use super::one::Ref;
 use crate::lock::{RwLockReadGuardDetached, RwLockWriteGuardDetached};
-use core::hash::Hash;
+use core::mem;
 use core::ops::{Deref, DerefMut};
-use std::sync::Arc;
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+
+/// The guard kept alive by shared references into a shard.
+///
+/// This is usually a read guard, but shared references split off a
+/// [`RefMut`](super::one::RefMut) next to mutable ones keep its write guard instead.
+#[derive(Clone)]
+pub(crate) enum SharedGuard<'a> {
+    Read(Arc<RwLockReadGuardDetached<'a>>),
+    Write(Arc<RwLockWriteGuardDetached<'a>>),
+}
 
 pub struct RefMulti<'a, K, V: ?Sized> {
-    pub(super) _guard: Arc<RwLockReadGuardDetached<'a>>,
+    pub(super) _guard: SharedGuard<'a>,
     pub(super) k: &'a K,
     pub(super) v: &'a V,
 }
 
-impl<'a, K: Eq + Hash, V: ?Sized> RefMulti<'a, K, V> {
+impl<'a, K, V: ?Sized> RefMulti<'a, K, V> {
     pub(crate) fn new(guard: Arc<RwLockReadGuardDetached<'a>>, k: &'a K, v: &'a V) -> Self {
         Self {
-            _guard: guard,
+            _guard: SharedGuard::Read(guard),
             k,
             v,
         }
@@ -29,9 +43,96 @@ impl<'a, K: Eq + Hash, V: ?Sized> RefMulti<'a, K, V> {
     pub fn pair(&self) -> (&K, &V) {
         (self.k, self.v)
     }
+
+    /// Clones the key and the value out of the map. The shard stays locked.
+    pub fn cloned(&self) -> (K, V)
+    where
+        K: Clone,
+        V: Clone,
+    {
+        (self.k.clone(), self.v.clone())
+    }
+
+    /// Clones the value out of the map. The shard stays locked.
+    pub fn value_cloned(&self) -> V
+    where
+        V: Clone,
+    {
+        self.v.clone()
+    }
+
+    /// Converts this handle back into a plain [`Ref`] if it is the last one sharing the shard guard.
+    ///
+    /// If the guard is still shared with other handles, `self` is returned unchanged.
+    pub fn try_into_single(self) -> Result<Ref<'a, K, V>, Self>
+    where
+        V: Sized,
+    {
+        let guard = match self._guard {
+            SharedGuard::Read(guard) => Arc::try_unwrap(guard).map_err(SharedGuard::Read),
+            SharedGuard::Write(guard) => Arc::try_unwrap(guard)
+                // SAFETY: this was the last handle, so nothing can mutate through the guard anymore.
+                .map(|guard| unsafe { RwLockWriteGuardDetached::downgrade(guard) })
+                .map_err(SharedGuard::Write),
+        };
+
+        match guard {
+            Ok(guard) => Ok(Ref::new_split(guard, self.k, self.v)),
+            Err(guard) => Err(Self {
+                _guard: guard,
+                k: self.k,
+                v: self.v,
+            }),
+        }
+    }
+
+    pub fn map<F, T: ?Sized>(self, f: F) -> MappedRefMulti<'a, K, T>
+    where
+        F: FnOnce(&V) -> &T,
+    {
+        MappedRefMulti {
+            _guard: self._guard,
+            k: self.k,
+            v: f(self.v),
+        }
+    }
+
+    pub fn try_map<F, T: ?Sized>(self, f: F) -> Result<MappedRefMulti<'a, K, T>, Self>
+    where
+        F: FnOnce(&V) -> Option<&T>,
+    {
+        if let Some(v) = f(self.v) {
+            Ok(MappedRefMulti {
+                _guard: self._guard,
+                k: self.k,
+                v,
+            })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<'a, K, V: ?Sized> Clone for RefMulti<'a, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            _guard: self._guard.clone(),
+            k: self.k,
+            v: self.v,
+        }
+    }
 }
 
-impl<'a, K: Eq + Hash, V: ?Sized> Deref for RefMulti<'a, K, V> {
+impl<'a, K: Debug, V: Debug + ?Sized> Debug for RefMulti<'a, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefMulti")
+            .field("k", &self.k)
+            .field("v", &self.v)
+            .finish()
+    }
+}
+
+impl<'a, K, V: ?Sized> Deref for RefMulti<'a, K, V> {
     type Target = V;
 
     fn deref(&self) -> &V {
@@ -39,13 +140,38 @@ impl<'a, K: Eq + Hash, V: ?Sized> Deref for RefMulti<'a, K, V> {
     }
 }
 
+impl<'a, K, V: std::fmt::Display + ?Sized> std::fmt::Display for RefMulti<'a, K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.value(), f)
+    }
+}
+
+impl<'a, K, V: ?Sized + AsRef<TDeref>, TDeref: ?Sized> AsRef<TDeref> for RefMulti<'a, K, V> {
+    fn as_ref(&self) -> &TDeref {
+        self.value().as_ref()
+    }
+}
+
+/// Unlike [`RefMulti`], this can not be cloned, as that would alias the mutable reference.
+///
+/// ```compile_fail
+/// use dashmap::DashMap;
+///
+/// fn assert_clone<T: Clone>(_: &T) {}
+///
+/// let map = DashMap::new();
+/// map.insert("a", 1);
+/// for r in map.iter_mut() {
+///     assert_clone(&r);
+/// }
+/// ```
 pub struct RefMutMulti<'a, K, V: ?Sized> {
     pub(super) _guard: Arc<RwLockWriteGuardDetached<'a>>,
     pub(super) k: &'a K,
     pub(super) v: &'a mut V,
 }
 
-impl<'a, K: Eq + Hash, V: ?Sized> RefMutMulti<'a, K, V> {
+impl<'a, K, V: ?Sized> RefMutMulti<'a, K, V> {
     pub(crate) fn new(guard: Arc<RwLockWriteGuardDetached<'a>>, k: &'a K, v: &'a mut V) -> Self {
         Self {
             _guard: guard,
@@ -73,9 +199,62 @@ impl<'a, K: Eq + Hash, V: ?Sized> RefMutMulti<'a, K, V> {
     pub fn pair_mut(&mut self) -> (&K, &mut V) {
         (self.k, self.v)
     }
+
+    /// Replaces the value, returning the old one. The shard stays locked.
+    pub fn replace(&mut self, value: V) -> V
+    where
+        V: Sized,
+    {
+        mem::replace(self.v, value)
+    }
+
+    /// Takes the value, leaving `V::default()` in its place. The shard stays locked.
+    pub fn take(&mut self) -> V
+    where
+        V: Default,
+    {
+        mem::take(self.v)
+    }
+
+    pub fn map<F, T: ?Sized>(self, f: F) -> MappedRefMutMulti<'a, K, T>
+    where
+        F: FnOnce(&mut V) -> &mut T,
+    {
+        MappedRefMutMulti {
+            _guard: self._guard,
+            k: self.k,
+            v: f(self.v),
+        }
+    }
+
+    pub fn try_map<F, T: ?Sized>(self, f: F) -> Result<MappedRefMutMulti<'a, K, T>, Self>
+    where
+        F: FnOnce(&mut V) -> Option<&mut T>,
+    {
+        let v = match f(unsafe { &mut *(self.v as *mut _) }) {
+            Some(v) => v,
+            None => return Err(self),
+        };
+        let guard = self._guard;
+        let k = self.k;
+        Ok(MappedRefMutMulti {
+            _guard: guard,
+            k,
+            v,
+        })
+    }
 }
 
-impl<'a, K: Eq + Hash, V: ?Sized> Deref for RefMutMulti<'a, K, V> {
+impl<'a, K: Debug, V: Debug + ?Sized> Debug for RefMutMulti<'a, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefMutMulti")
+            .field("k", &self.k)
+            .field("v", &self.v)
+            .finish()
+    }
+}
+
+impl<'a, K, V: ?Sized> Deref for RefMutMulti<'a, K, V> {
     type Target = V;
 
     fn deref(&self) -> &V {
@@ -83,8 +262,407 @@ impl<'a, K: Eq + Hash, V: ?Sized> Deref for RefMutMulti<'a, K, V> {
     }
 }
 
-impl<'a, K: Eq + Hash, V: ?Sized> DerefMut for RefMutMulti<'a, K, V> {
+impl<'a, K, V: ?Sized> DerefMut for RefMutMulti<'a, K, V> {
     fn deref_mut(&mut self) -> &mut V {
         self.value_mut()
     }
 }
+
+impl<'a, K, V: std::fmt::Display + ?Sized> std::fmt::Display for RefMutMulti<'a, K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.value(), f)
+    }
+}
+
+impl<'a, K, V: ?Sized + AsRef<TDeref>, TDeref: ?Sized> AsRef<TDeref> for RefMutMulti<'a, K, V> {
+    fn as_ref(&self) -> &TDeref {
+        self.value().as_ref()
+    }
+}
+
+pub struct MappedRefMulti<'a, K, T: ?Sized> {
+    pub(super) _guard: SharedGuard<'a>,
+    pub(super) k: &'a K,
+    pub(super) v: &'a T,
+}
+
+impl<'a, K, T: ?Sized> MappedRefMulti<'a, K, T> {
+    pub fn key(&self) -> &K {
+        self.pair().0
+    }
+
+    pub fn value(&self) -> &T {
+        self.pair().1
+    }
+
+    pub fn pair(&self) -> (&K, &T) {
+        (self.k, self.v)
+    }
+
+    pub fn map<F, T2: ?Sized>(self, f: F) -> MappedRefMulti<'a, K, T2>
+    where
+        F: FnOnce(&T) -> &T2,
+    {
+        MappedRefMulti {
+            _guard: self._guard,
+            k: self.k,
+            v: f(self.v),
+        }
+    }
+
+    pub fn try_map<F, T2: ?Sized>(self, f: F) -> Result<MappedRefMulti<'a, K, T2>, Self>
+    where
+        F: FnOnce(&T) -> Option<&T2>,
+    {
+        let v = match f(self.v) {
+            Some(v) => v,
+            None => return Err(self),
+        };
+        Ok(MappedRefMulti {
+            _guard: self._guard,
+            k: self.k,
+            v,
+        })
+    }
+}
+
+impl<'a, K, T: ?Sized> Clone for MappedRefMulti<'a, K, T> {
+    fn clone(&self) -> Self {
+        Self {
+            _guard: self._guard.clone(),
+            k: self.k,
+            v: self.v,
+        }
+    }
+}
+
+impl<'a, K: Debug, T: Debug + ?Sized> Debug for MappedRefMulti<'a, K, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedRefMulti")
+            .field("k", &self.k)
+            .field("v", &self.v)
+            .finish()
+    }
+}
+
+impl<'a, K, T: ?Sized> Deref for MappedRefMulti<'a, K, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value()
+    }
+}
+
+impl<'a, K, T: std::fmt::Display + ?Sized> std::fmt::Display for MappedRefMulti<'a, K, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.value(), f)
+    }
+}
+
+impl<'a, K, T: ?Sized + AsRef<TDeref>, TDeref: ?Sized> AsRef<TDeref> for MappedRefMulti<'a, K, T> {
+    fn as_ref(&self) -> &TDeref {
+        self.value().as_ref()
+    }
+}
+
+pub struct MappedRefMutMulti<'a, K, T: ?Sized> {
+    pub(super) _guard: Arc<RwLockWriteGuardDetached<'a>>,
+    pub(super) k: &'a K,
+    pub(super) v: &'a mut T,
+}
+
+impl<'a, K, T: ?Sized> MappedRefMutMulti<'a, K, T> {
+    pub fn key(&self) -> &K {
+        self.pair().0
+    }
+
+    pub fn value(&self) -> &T {
+        self.pair().1
+    }
+
+    pub fn value_mut(&mut self) -> &mut T {
+        self.pair_mut().1
+    }
+
+    pub fn pair(&self) -> (&K, &T) {
+        (self.k, self.v)
+    }
+
+    pub fn pair_mut(&mut self) -> (&K, &mut T) {
+        (self.k, self.v)
+    }
+
+    pub fn map<F, T2: ?Sized>(self, f: F) -> MappedRefMutMulti<'a, K, T2>
+    where
+        F: FnOnce(&mut T) -> &mut T2,
+    {
+        MappedRefMutMulti {
+            _guard: self._guard,
+            k: self.k,
+            v: f(self.v),
+        }
+    }
+
+    pub fn try_map<F, T2: ?Sized>(self, f: F) -> Result<MappedRefMutMulti<'a, K, T2>, Self>
+    where
+        F: FnOnce(&mut T) -> Option<&mut T2>,
+    {
+        let v = match f(unsafe { &mut *(self.v as *mut _) }) {
+            Some(v) => v,
+            None => return Err(self),
+        };
+        let guard = self._guard;
+        let k = self.k;
+        Ok(MappedRefMutMulti {
+            _guard: guard,
+            k,
+            v,
+        })
+    }
+}
+
+impl<'a, K: Debug, T: Debug + ?Sized> Debug for MappedRefMutMulti<'a, K, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedRefMutMulti")
+            .field("k", &self.k)
+            .field("v", &self.v)
+            .finish()
+    }
+}
+
+impl<'a, K, T: ?Sized> Deref for MappedRefMutMulti<'a, K, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value()
+    }
+}
+
+impl<'a, K, T: ?Sized> DerefMut for MappedRefMutMulti<'a, K, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value_mut()
+    }
+}
+
+impl<'a, K, T: std::fmt::Display + ?Sized> std::fmt::Display for MappedRefMutMulti<'a, K, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.value(), f)
+    }
+}
+
+impl<'a, K, T: ?Sized + AsRef<TDeref>, TDeref: ?Sized> AsRef<TDeref>
+    for MappedRefMutMulti<'a, K, T>
+{
+    fn as_ref(&self) -> &TDeref {
+        self.value().as_ref()
+    }
+}
+
+impl_cmp!(RefMulti: ?Sized);
+impl_cmp!(RefMutMulti: ?Sized);
+impl_cmp!(MappedRefMulti: ?Sized);
+impl_cmp!(MappedRefMutMulti: ?Sized);
+
+#[cfg(test)]
+mod tests {
+    use crate::DashMap;
+
+    #[test]
+    fn try_into_single() {
+        let data = DashMap::new();
+        data.insert("test", ("hello", "world"));
+        let (l_ref, r_ref) = data.get("test").unwrap().map_split(|d| (&d.0, &d.1));
+
+        let Err(l_ref) = l_ref.try_into_single() else {
+            panic!("guard is still shared")
+        };
+        drop(r_ref);
+        let Ok(l_ref) = l_ref.try_into_single() else {
+            panic!("guard is no longer shared")
+        };
+        assert_eq!(*l_ref.value(), "hello");
+    }
+
+    #[test]
+    fn try_into_single_from_write() {
+        let data = DashMap::new();
+        data.insert("test", (1, 2));
+        let (mut l_ref, r_ref) = data
+            .get_mut("test")
+            .unwrap()
+            .map_mut_and_ref(|d| (&mut d.0, &d.1));
+        *l_ref += *r_ref;
+        drop(l_ref);
+
+        let Ok(r_ref) = r_ref.try_into_single() else {
+            panic!("guard is no longer shared")
+        };
+        assert_eq!(*r_ref, 2);
+        assert!(data.try_get("test").is_present());
+    }
+
+    #[test]
+    fn formatting() {
+        let data = DashMap::new();
+        data.insert("test", "hello".to_string());
+
+        let r = data.iter().next().unwrap();
+        assert_eq!(format!("{r:?}"), r#"RefMulti { k: "test", v: "hello" }"#);
+        assert_eq!(r.to_string(), "hello");
+        assert_eq!(AsRef::<str>::as_ref(&r), "hello");
+
+        let m = r.map(|s| s.as_str());
+        assert_eq!(
+            format!("{m:?}"),
+            r#"MappedRefMulti { k: "test", v: "hello" }"#
+        );
+        assert_eq!(m.to_string(), "hello");
+        assert_eq!(AsRef::<[u8]>::as_ref(&m), b"hello");
+        drop(m);
+
+        let r = data.iter_mut().next().unwrap();
+        assert_eq!(format!("{r:?}"), r#"RefMutMulti { k: "test", v: "hello" }"#);
+        assert_eq!(r.to_string(), "hello");
+        assert_eq!(AsRef::<str>::as_ref(&r), "hello");
+
+        let m = r.map(|s| s.as_mut_str());
+        assert_eq!(
+            format!("{m:?}"),
+            r#"MappedRefMutMulti { k: "test", v: "hello" }"#
+        );
+        assert_eq!(m.to_string(), "hello");
+        assert_eq!(AsRef::<[u8]>::as_ref(&m), b"hello");
+    }
+
+    #[test]
+    fn ref_multi_cloned() {
+        let data = DashMap::new();
+        data.insert(1, String::from("a"));
+        data.insert(2, String::from("b"));
+
+        let mut pairs = data.iter().map(|r| r.cloned()).collect::<Vec<_>>();
+        pairs.sort();
+        assert_eq!(pairs, [(1, String::from("a")), (2, String::from("b"))]);
+
+        let r = data.iter().next().unwrap();
+        assert_eq!(r.value_cloned(), *r.value());
+    }
+
+    #[test]
+    fn compare() {
+        let data = DashMap::new();
+        data.insert("test", 1);
+        let one = &1;
+        let (a_ref, b_ref) = data.get("test").unwrap().map_split(|v| (v, v));
+        assert!(a_ref == 1);
+        assert!(a_ref == one);
+        assert!(a_ref < 2);
+        assert!(a_ref == b_ref);
+        drop((a_ref, b_ref));
+
+        for r in data.iter_mut() {
+            assert!(r == 1);
+            assert!(r >= one);
+        }
+    }
+
+    #[test]
+    fn mapped_ref_multi() {
+        let data = DashMap::new();
+        data.insert("test", *b"hello world");
+        for r in data.iter() {
+            let Ok(s_ref) = r.try_map(|b| std::str::from_utf8(b).ok()) else {
+                panic!("value is valid utf-8")
+            };
+            let Ok(hello_ref) = s_ref.try_map(|s| s.get(..5)) else {
+                panic!("value is at least 5 bytes long")
+            };
+
+            assert_eq!(*hello_ref.key(), "test");
+            assert_eq!(hello_ref.value(), "hello");
+        }
+    }
+
+    #[test]
+    fn ref_mut_multi_replace() {
+        let data = DashMap::new();
+        data.insert("test", 1);
+        for mut r in data.iter_mut() {
+            assert_eq!(r.replace(2), 1);
+            assert_eq!(*r.key(), "test");
+        }
+        assert_eq!(*data.get("test").unwrap(), 2);
+    }
+
+    #[test]
+    fn ref_mut_multi_take() {
+        let data = DashMap::new();
+        data.insert("test", 1);
+        for mut r in data.iter_mut() {
+            assert_eq!(r.take(), 1);
+            assert_eq!(*r, 0);
+        }
+        assert_eq!(*data.get("test").unwrap(), 0);
+    }
+
+    #[test]
+    fn mapped_ref_mut_multi() {
+        let data = DashMap::new();
+        data.insert("test", *b"test");
+        for b_ref in data.iter_mut() {
+            let Ok(mut s_ref) = b_ref.try_map(|b| std::str::from_utf8_mut(b).ok()) else {
+                panic!("value is valid utf-8")
+            };
+            s_ref.value_mut().make_ascii_uppercase();
+        }
+
+        assert_eq!(data.get("test").unwrap().value(), b"TEST");
+    }
+
+    #[test]
+    fn mapped_ref_mut_multi_again() {
+        let data = DashMap::new();
+        data.insert("test", *b"hello world");
+        for b_ref in data.iter_mut() {
+            let Ok(s_ref) = b_ref.try_map(|b| std::str::from_utf8_mut(b).ok()) else {
+                panic!("value is valid utf-8")
+            };
+            let Ok(mut hello_ref) = s_ref.try_map(|s| s.get_mut(..5)) else {
+                panic!("value is at least 5 bytes long")
+            };
+            assert_eq!(*hello_ref.key(), "test");
+            hello_ref.make_ascii_uppercase();
+        }
+
+        assert_eq!(data.get("test").unwrap().value(), b"HELLO world");
+    }
+
+    #[test]
+    fn ref_multi_clone() {
+        let data = DashMap::new();
+        data.insert("test", "hello world".to_string());
+        let r_ref = data.iter().next().unwrap();
+        let s_ref = r_ref.clone().map(|s| &s[..5]);
+        let t_ref = s_ref.clone();
+        drop(r_ref);
+        drop(s_ref);
+
+        assert_eq!(t_ref.value(), "hello");
+        assert!(data.try_get_mut("test").is_locked());
+        drop(t_ref);
+        assert!(data.try_get_mut("test").is_present());
+    }
+
+    #[test]
+    fn mapped_ref_multi_failure() {
+        let data = DashMap::new();
+        data.insert("test", *b"\xff");
+        for r in data.iter() {
+            let Err(r) = r.try_map(|b| std::str::from_utf8(b).ok()) else {
+                panic!("value is not valid utf-8")
+            };
+
+            assert_eq!(r.value(), b"\xff");
+        }
+    }
+}
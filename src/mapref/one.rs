@@ -1,26 +1,41 @@
 use crate::{
     lock::{RwLockReadGuardDetached, RwLockWriteGuardDetached},
-    mapref::multiple::{RefMulti, RefMutMulti},
+    mapref::multiple::{RefMulti, RefMutMulti, SharedGuard},
+    HashMap,
 };
-use core::hash::Hash;
 use core::ops::{Deref, DerefMut};
+use core::{mem, ptr};
 use std::{
     fmt::{Debug, Formatter},
     sync::Arc,
 };
 
 pub struct Ref<'a, K, V> {
-    _guard: RwLockReadGuardDetached<'a>,
+    guard: RwLockReadGuardDetached<'a>,
     k: &'a K,
     v: &'a V,
+    // Whether `k` and `v` are an entry of the locked `HashMap<K, V>`,
+    // rather than something split off from one.
+    in_shard: bool,
 }
 
-impl<'a, K: Eq + Hash, V> Ref<'a, K, V> {
+impl<'a, K, V> Ref<'a, K, V> {
     pub(crate) fn new(guard: RwLockReadGuardDetached<'a>, k: &'a K, v: &'a V) -> Self {
         Self {
-            _guard: guard,
+            guard,
             k,
             v,
+            in_shard: true,
+        }
+    }
+
+    /// Like [`Ref::new`], for a value that may not be stored in the shard as is.
+    pub(crate) fn new_split(guard: RwLockReadGuardDetached<'a>, k: &'a K, v: &'a V) -> Self {
+        Self {
+            guard,
+            k,
+            v,
+            in_shard: false,
         }
     }
 
@@ -36,12 +51,82 @@ impl<'a, K: Eq + Hash, V> Ref<'a, K, V> {
         (self.k, self.v)
     }
 
+    /// Clones the key and value out of the map, releasing the shard lock.
+    pub fn into_owned(self) -> (K, V)
+    where
+        K: Clone,
+        V: Clone,
+    {
+        (self.k.clone(), self.v.clone())
+    }
+
+    /// Clones the key and the value out of the map. The shard stays locked.
+    pub fn cloned(&self) -> (K, V)
+    where
+        K: Clone,
+        V: Clone,
+    {
+        (self.k.clone(), self.v.clone())
+    }
+
+    /// Clones the value out of the map. The shard stays locked.
+    pub fn value_cloned(&self) -> V
+    where
+        V: Clone,
+    {
+        self.v.clone()
+    }
+
+    /// Creates another read reference to the same entry.
+    ///
+    /// The shard stays read-locked until both this `Ref` and every returned handle are dropped.
+    /// No lookup is performed and the shard lock is never waited on.
+    pub fn clone_ref(&self) -> RefMulti<'a, K, V> {
+        RefMulti::new(Arc::new(self.guard.clone()), self.k, self.v)
+    }
+
+    /// Turns this into a [`RefMulti`], which can be cloned cheaply to share the entry.
+    ///
+    /// The shard lock is kept as is. Use [`RefMulti::try_into_single`] to go back
+    /// once all other clones are dropped.
+    pub fn into_multi(self) -> RefMulti<'a, K, V> {
+        RefMulti::new(Arc::new(self.guard), self.k, self.v)
+    }
+
+    /// Tries to turn this into a [`RefMut`] without unlocking the shard in between,
+    /// so no one else can change or remove the entry in the meantime.
+    ///
+    /// This fails and hands back `self` if any other reference keeps the shard read-locked,
+    /// or if this reference was split off from another one. Finding the entry again takes
+    /// time linear in the number of entries in the shard.
+    pub fn try_upgrade(self) -> Result<RefMut<'a, K, V>, Self> {
+        if !self.in_shard {
+            return Err(self);
+        }
+
+        let key: *const K = self.k;
+        // SAFETY: `self.k` and `self.v` are not used after upgrading.
+        let guard = match unsafe { self.guard.try_upgrade() } {
+            Ok(guard) => guard,
+            Err(guard) => return Err(Self { guard, ..self }),
+        };
+
+        // SAFETY: The shard is exclusively locked now, and `in_shard` says that it is a
+        // `HashMap<K, V>` containing the entry.
+        let shard = unsafe { &mut *guard.data_ptr::<HashMap<K, V>>() };
+        let (k, v) = shard
+            .iter_mut()
+            .find(|(k, _v)| ptr::eq(k, key))
+            .expect("locked entry vanished from its shard");
+        Ok(RefMut::new(guard, k, v))
+    }
+
     pub fn map<F, T: ?Sized>(self, f: F) -> MappedRef<'a, K, T>
     where
         F: FnOnce(&V) -> &T,
     {
         MappedRef {
-            _guard: self._guard,
+            _guard: self.guard,
             k: self.k,
             v: f(self.v),
         }
@@ -53,7 +138,7 @@ impl<'a, K: Eq + Hash, V> Ref<'a, K, V> {
     {
         if let Some(v) = f(self.v) {
             Ok(MappedRef {
-                _guard: self._guard,
+                _guard: self.guard,
                 k: self.k,
                 v,
             })
@@ -70,23 +155,42 @@ impl<'a, K: Eq + Hash, V> Ref<'a, K, V> {
         F: FnOnce(&V) -> (&A, &B),
     {
         let (a, b) = f(self.v);
-        let guard = Arc::new(self._guard);
+        let guard = Arc::new(self.guard);
         (
-            RefMulti {
-                _guard: guard.clone(),
-                k: self.k,
-                v: a,
-            },
-            RefMulti {
-                _guard: guard,
-                k: self.k,
-                v: b,
-            },
+            RefMulti::new(guard.clone(), self.k, a),
+            RefMulti::new(guard, self.k, b),
         )
     }
+
+    pub fn try_map_split<F, A: ?Sized, B: ?Sized>(
+        self,
+        f: F,
+    ) -> Result<(RefMulti<'a, K, A>, RefMulti<'a, K, B>), Self>
+    where
+        F: FnOnce(&V) -> Option<(&A, &B)>,
+    {
+        let (a, b) = match f(self.v) {
+            Some(split) => split,
+            None => return Err(self),
+        };
+        let guard = Arc::new(self.guard);
+        Ok((
+            RefMulti::new(guard.clone(), self.k, a),
+            RefMulti::new(guard, self.k, b),
+        ))
+    }
+
+    /// Like [`Ref::map_split`], but splits into any number of references sharing a single guard.
+    pub fn split_with<F, T: ?Sized, const N: usize>(self, f: F) -> [RefMulti<'a, K, T>; N]
+    where
+        F: FnOnce(&V) -> [&T; N],
+    {
+        let guard = Arc::new(self.guard);
+        f(self.v).map(|v| RefMulti::new(guard.clone(), self.k, v))
+    }
 }
 
-impl<'a, K: Eq + Hash + Debug, V: Debug> Debug for Ref<'a, K, V> {
+impl<'a, K: Debug, V: Debug> Debug for Ref<'a, K, V> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Ref")
             .field("k", &self.k)
@@ -95,7 +199,7 @@ impl<'a, K: Eq + Hash + Debug, V: Debug> Debug for Ref<'a, K, V> {
     }
 }
 
-impl<'a, K: Eq + Hash, V> Deref for Ref<'a, K, V> {
+impl<'a, K, V> Deref for Ref<'a, K, V> {
     type Target = V;
 
     fn deref(&self) -> &V {
@@ -103,13 +207,32 @@ impl<'a, K: Eq + Hash, V> Deref for Ref<'a, K, V> {
     }
 }
 
+/// ```
+/// use dashmap::DashMap;
+///
+/// let map = DashMap::new();
+/// map.insert("greeting", "hello");
+/// assert_eq!(format!("{}", map.get("greeting").unwrap()), "hello");
+/// ```
+impl<'a, K, V: std::fmt::Display> std::fmt::Display for Ref<'a, K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.value(), f)
+    }
+}
+
+impl<'a, K, V: AsRef<TDeref>, TDeref: ?Sized> AsRef<TDeref> for Ref<'a, K, V> {
+    fn as_ref(&self) -> &TDeref {
+        self.value().as_ref()
+    }
+}
+
 pub struct RefMut<'a, K, V> {
     guard: RwLockWriteGuardDetached<'a>,
     k: &'a K,
     v: &'a mut V,
 }
 
-impl<'a, K: Eq + Hash, V> RefMut<'a, K, V> {
+impl<'a, K, V> RefMut<'a, K, V> {
     pub(crate) fn new(guard: RwLockWriteGuardDetached<'a>, k: &'a K, v: &'a mut V) -> Self {
         Self { guard, k, v }
     }
@@ -134,6 +257,19 @@ impl<'a, K: Eq + Hash, V> RefMut<'a, K, V> {
         (self.k, self.v)
     }
 
+    /// Replaces the value, returning the old one. The shard stays locked.
+    pub fn replace(&mut self, value: V) -> V {
+        mem::replace(self.v, value)
+    }
+
+    /// Takes the value, leaving `V::default()` in its place. The shard stays locked.
+    pub fn take(&mut self) -> V
+    where
+        V: Default,
+    {
+        mem::take(self.v)
+    }
+
     pub fn downgrade(self) -> Ref<'a, K, V> {
         Ref::new(
             unsafe { RwLockWriteGuardDetached::downgrade(self.guard) },
@@ -142,6 +278,22 @@ impl<'a, K: Eq + Hash, V> RefMut<'a, K, V> {
         )
     }
 
+    /// Maps the value to a shared projection and downgrades the write lock to a read lock.
+    ///
+    /// The closure still has mutable access, so it can finish updating the value before
+    /// handing out the part that should stay readable.
+    pub fn downgrade_map<F, T: ?Sized>(self, f: F) -> MappedRef<'a, K, T>
+    where
+        F: FnOnce(&mut V) -> &T,
+    {
+        let v = f(self.v);
+        MappedRef {
+            _guard: unsafe { RwLockWriteGuardDetached::downgrade(self.guard) },
+            k: self.k,
+            v,
+        }
+    }
+
     pub fn map<F, T: ?Sized>(self, f: F) -> MappedRefMut<'a, K, T>
     where
         F: FnOnce(&mut V) -> &mut T,
@@ -192,9 +344,117 @@ impl<'a, K: Eq + Hash, V> RefMut<'a, K, V> {
             },
         )
     }
+
+    pub fn try_map_split<F, A: ?Sized, B: ?Sized>(
+        self,
+        f: F,
+    ) -> Result<(RefMutMulti<'a, K, A>, RefMutMulti<'a, K, B>), Self>
+    where
+        F: FnOnce(&mut V) -> Option<(&mut A, &mut B)>,
+    {
+        let (a, b) = match f(unsafe { &mut *(self.v as *mut _) }) {
+            Some(split) => split,
+            None => return Err(self),
+        };
+        let guard = Arc::new(self.guard);
+        Ok((
+            RefMutMulti {
+                _guard: guard.clone(),
+                k: self.k,
+                v: a,
+            },
+            RefMutMulti {
+                _guard: guard,
+                k: self.k,
+                v: b,
+            },
+        ))
+    }
+
+    /// Like [`RefMut::map_split`], but splits into any number of disjoint mutable references
+    /// sharing a single guard.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("rgb", [0u8; 3]);
+    ///
+    /// let [mut r, mut g, mut b] = map.get_mut("rgb").unwrap().split_with(|[r, g, b]| [r, g, b]);
+    /// *r = 1;
+    /// *g = 2;
+    /// *b = 3;
+    /// drop((r, g, b));
+    ///
+    /// assert_eq!(*map.get("rgb").unwrap(), [1, 2, 3]);
+    /// ```
+    pub fn split_with<F, T: ?Sized, const N: usize>(self, f: F) -> [RefMutMulti<'a, K, T>; N]
+    where
+        F: FnOnce(&mut V) -> [&mut T; N],
+    {
+        let guard = Arc::new(self.guard);
+        f(self.v).map(|v| RefMutMulti::new(guard.clone(), self.k, v))
+    }
+
+    /// Splits a slice-like value into two disjoint mutable halves at `mid`,
+    /// like [`slice::split_at_mut`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is greater than the length of the slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("buf", vec![0; 4]);
+    ///
+    /// let (mut head, mut tail) = map.get_mut("buf").unwrap().map_split_at(1);
+    /// head.fill(1);
+    /// tail.fill(2);
+    /// drop((head, tail));
+    ///
+    /// assert_eq!(*map.get("buf").unwrap(), [1, 2, 2, 2]);
+    /// ```
+    pub fn map_split_at<T>(self, mid: usize) -> (RefMutMulti<'a, K, [T]>, RefMutMulti<'a, K, [T]>)
+    where
+        V: AsMut<[T]>,
+    {
+        self.map_split(|v| v.as_mut().split_at_mut(mid))
+    }
+
+    /// Splits the value into a mutable and a shared part.
+    ///
+    /// Both halves keep the shard write-locked until the last one is dropped.
+    pub fn map_mut_and_ref<F, A: ?Sized, B: ?Sized>(
+        self,
+        f: F,
+    ) -> (RefMutMulti<'a, K, A>, RefMulti<'a, K, B>)
+    where
+        F: FnOnce(&mut V) -> (&mut A, &B),
+    {
+        let (a, b) = f(self.v);
+        let guard = Arc::new(self.guard);
+        (
+            RefMutMulti {
+                _guard: guard.clone(),
+                k: self.k,
+                v: a,
+            },
+            RefMulti {
+                _guard: SharedGuard::Write(guard),
+                k: self.k,
+                v: b,
+            },
+        )
+    }
 }
 
-impl<'a, K: Eq + Hash + Debug, V: Debug> Debug for RefMut<'a, K, V> {
+impl<'a, K: Debug, V: Debug> Debug for RefMut<'a, K, V> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RefMut")
             .field("k", &self.k)
@@ -203,7 +463,7 @@ impl<'a, K: Eq + Hash + Debug, V: Debug> Debug for RefMut<'a, K, V> {
     }
 }
 
-impl<'a, K: Eq + Hash, V> Deref for RefMut<'a, K, V> {
+impl<'a, K, V> Deref for RefMut<'a, K, V> {
     type Target = V;
 
     fn deref(&self) -> &V {
@@ -211,19 +471,38 @@ impl<'a, K: Eq + Hash, V> Deref for RefMut<'a, K, V> {
     }
 }
 
-impl<'a, K: Eq + Hash, V> DerefMut for RefMut<'a, K, V> {
+impl<'a, K, V> DerefMut for RefMut<'a, K, V> {
     fn deref_mut(&mut self) -> &mut V {
         self.value_mut()
     }
 }
 
+/// ```
+/// use dashmap::DashMap;
+///
+/// let map = DashMap::new();
+/// map.insert("answer", 42);
+/// assert_eq!(map.get_mut("answer").unwrap().to_string(), "42");
+/// ```
+impl<'a, K, V: std::fmt::Display> std::fmt::Display for RefMut<'a, K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.value(), f)
+    }
+}
+
+impl<'a, K, V: AsRef<TDeref>, TDeref: ?Sized> AsRef<TDeref> for RefMut<'a, K, V> {
+    fn as_ref(&self) -> &TDeref {
+        self.value().as_ref()
+    }
+}
+
 pub struct MappedRef<'a, K, T: ?Sized> {
     _guard: RwLockReadGuardDetached<'a>,
     k: &'a K,
     v: &'a T,
 }
 
-impl<'a, K: Eq + Hash, T: ?Sized> MappedRef<'a, K, T> {
+impl<'a, K, T: ?Sized> MappedRef<'a, K, T> {
     pub fn key(&self) -> &K {
         self.pair().0
     }
@@ -236,6 +515,32 @@ impl<'a, K: Eq + Hash, T: ?Sized> MappedRef<'a, K, T> {
         (self.k, self.v)
     }
 
+    /// Clones the key and the mapped value out of the map, releasing the shard lock.
+    pub fn into_owned(self) -> (K, T)
+    where
+        K: Clone,
+        T: Clone,
+    {
+        (self.k.clone(), self.v.clone())
+    }
+
+    /// Clones the key and the mapped value out of the map. The shard stays locked.
+    pub fn cloned(&self) -> (K, T)
+    where
+        K: Clone,
+        T: Clone,
+    {
+        (self.k.clone(), self.v.clone())
+    }
+
+    /// Clones the mapped value out of the map. The shard stays locked.
+    pub fn value_cloned(&self) -> T
+    where
+        T: Clone,
+    {
+        self.v.clone()
+    }
+
     pub fn map<F, T2>(self, f: F) -> MappedRef<'a, K, T2>
     where
         F: FnOnce(&T) -> &T2,
@@ -262,9 +567,42 @@ impl<'a, K: Eq + Hash, T: ?Sized> MappedRef<'a, K, T> {
             v,
         })
     }
+
+    pub fn map_split<F, A: ?Sized, B: ?Sized>(
+        self,
+        f: F,
+    ) -> (RefMulti<'a, K, A>, RefMulti<'a, K, B>)
+    where
+        F: FnOnce(&T) -> (&A, &B),
+    {
+        let (a, b) = f(self.v);
+        let guard = Arc::new(self._guard);
+        (
+            RefMulti::new(guard.clone(), self.k, a),
+            RefMulti::new(guard, self.k, b),
+        )
+    }
+
+    pub fn try_map_split<F, A: ?Sized, B: ?Sized>(
+        self,
+        f: F,
+    ) -> Result<(RefMulti<'a, K, A>, RefMulti<'a, K, B>), Self>
+    where
+        F: FnOnce(&T) -> Option<(&A, &B)>,
+    {
+        let (a, b) = match f(self.v) {
+            Some(split) => split,
+            None => return Err(self),
+        };
+        let guard = Arc::new(self._guard);
+        Ok((
+            RefMulti::new(guard.clone(), self.k, a),
+            RefMulti::new(guard, self.k, b),
+        ))
+    }
 }
 
-impl<'a, K: Eq + Hash + Debug, T: Debug + ?Sized> Debug for MappedRef<'a, K, T> {
+impl<'a, K: Debug, T: Debug + ?Sized> Debug for MappedRef<'a, K, T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MappedRef")
             .field("k", &self.k)
@@ -273,7 +611,7 @@ impl<'a, K: Eq + Hash + Debug, T: Debug + ?Sized> Debug for MappedRef<'a, K, T>
     }
 }
 
-impl<'a, K: Eq + Hash, T: ?Sized> Deref for MappedRef<'a, K, T> {
+impl<'a, K, T: ?Sized> Deref for MappedRef<'a, K, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
@@ -281,15 +619,13 @@ impl<'a, K: Eq + Hash, T: ?Sized> Deref for MappedRef<'a, K, T> {
     }
 }
 
-impl<'a, K: Eq + Hash, T: std::fmt::Display + ?Sized> std::fmt::Display for MappedRef<'a, K, T> {
+impl<'a, K, T: std::fmt::Display + ?Sized> std::fmt::Display for MappedRef<'a, K, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.value(), f)
     }
 }
 
-impl<'a, K: Eq + Hash, T: ?Sized + AsRef<TDeref>, TDeref: ?Sized> AsRef<TDeref>
-    for MappedRef<'a, K, T>
-{
+impl<'a, K, T: ?Sized + AsRef<TDeref>, TDeref: ?Sized> AsRef<TDeref> for MappedRef<'a, K, T> {
     fn as_ref(&self) -> &TDeref {
         self.value().as_ref()
     }
@@ -301,7 +637,7 @@ pub struct MappedRefMut<'a, K, T: ?Sized> {
     v: &'a mut T,
 }
 
-impl<'a, K: Eq + Hash, T: ?Sized> MappedRefMut<'a, K, T> {
+impl<'a, K, T: ?Sized> MappedRefMut<'a, K, T> {
     pub fn key(&self) -> &K {
         self.pair().0
     }
@@ -322,6 +658,30 @@ impl<'a, K: Eq + Hash, T: ?Sized> MappedRefMut<'a, K, T> {
         (self.k, self.v)
     }
 
+    /// Replaces the mapped value, returning the old one. The shard stays locked.
+    pub fn replace(&mut self, value: T) -> T
+    where
+        T: Sized,
+    {
+        mem::replace(self.v, value)
+    }
+
+    /// Takes the mapped value, leaving `T::default()` in its place. The shard stays locked.
+    pub fn take(&mut self) -> T
+    where
+        T: Default,
+    {
+        mem::take(self.v)
+    }
+
+    pub fn downgrade(self) -> MappedRef<'a, K, T> {
+        MappedRef {
+            _guard: unsafe { RwLockWriteGuardDetached::downgrade(self._guard) },
+            k: self.k,
+            v: self.v,
+        }
+    }
+
     pub fn map<F, T2: ?Sized>(self, f: F) -> MappedRefMut<'a, K, T2>
     where
         F: FnOnce(&mut T) -> &mut T2,
@@ -349,9 +709,32 @@ impl<'a, K: Eq + Hash, T: ?Sized> MappedRefMut<'a, K, T> {
             v,
         })
     }
+
+    pub fn map_split<F, A: ?Sized, B: ?Sized>(
+        self,
+        f: F,
+    ) -> (RefMutMulti<'a, K, A>, RefMutMulti<'a, K, B>)
+    where
+        F: FnOnce(&mut T) -> (&mut A, &mut B),
+    {
+        let (a, b) = f(self.v);
+        let guard = Arc::new(self._guard);
+        (
+            RefMutMulti {
+                _guard: guard.clone(),
+                k: self.k,
+                v: a,
+            },
+            RefMutMulti {
+                _guard: guard,
+                k: self.k,
+                v: b,
+            },
+        )
+    }
 }
 
-impl<'a, K: Eq + Hash + Debug, T: Debug + ?Sized> Debug for MappedRefMut<'a, K, T> {
+impl<'a, K: Debug, T: Debug + ?Sized> Debug for MappedRefMut<'a, K, T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MappedRefMut")
             .field("k", &self.k)
@@ -360,7 +743,7 @@ impl<'a, K: Eq + Hash + Debug, T: Debug + ?Sized> Debug for MappedRefMut<'a, K,
     }
 }
 
-impl<'a, K: Eq + Hash, T: ?Sized> Deref for MappedRefMut<'a, K, T> {
+impl<'a, K, T: ?Sized> Deref for MappedRefMut<'a, K, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
@@ -368,16 +751,209 @@ impl<'a, K: Eq + Hash, T: ?Sized> Deref for MappedRefMut<'a, K, T> {
     }
 }
 
-impl<'a, K: Eq + Hash, T: ?Sized> DerefMut for MappedRefMut<'a, K, T> {
+impl<'a, K, T: ?Sized> DerefMut for MappedRefMut<'a, K, T> {
     fn deref_mut(&mut self) -> &mut T {
         self.value_mut()
     }
 }
 
+impl<'a, K, T: std::fmt::Display + ?Sized> std::fmt::Display for MappedRefMut<'a, K, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.value(), f)
+    }
+}
+
+impl<'a, K, T: ?Sized + AsRef<TDeref>, TDeref: ?Sized> AsRef<TDeref> for MappedRefMut<'a, K, T> {
+    fn as_ref(&self) -> &TDeref {
+        self.value().as_ref()
+    }
+}
+
+impl_cmp!(Ref: Sized);
+impl_cmp!(RefMut: Sized);
+impl_cmp!(MappedRef: ?Sized);
+impl_cmp!(MappedRefMut: ?Sized);
+
 #[cfg(test)]
 mod tests {
     use crate::DashMap;
 
+    #[test]
+    fn no_key_bounds() {
+        use super::{Ref, RefMut};
+        use std::fmt::Display;
+
+        // None of these helpers may require `K: Eq + Hash`.
+        fn read<K, V: Copy>(r: &Ref<'_, K, V>) -> V {
+            **r
+        }
+        fn write<K, V>(r: &mut RefMut<'_, K, V>, v: V) {
+            **r = v;
+        }
+        fn show<K, T: Display + ?Sized>(r: &super::MappedRef<'_, K, T>) -> String {
+            r.to_string()
+        }
+
+        let data = DashMap::new();
+        data.insert("test", 1);
+        write(&mut data.get_mut("test").unwrap(), 2);
+        assert_eq!(read(&data.get("test").unwrap()), 2);
+        assert_eq!(show(&data.get("test").unwrap().map(|v| v)), "2");
+    }
+
+    #[test]
+    fn mapped_mut_formatting() {
+        let data = DashMap::new();
+        data.insert("test", "hello".to_string());
+
+        let m = data.get_mut("test").unwrap().map(|s| s.as_mut_str());
+        assert_eq!(
+            format!("{m:?}"),
+            r#"MappedRefMut { k: "test", v: "hello" }"#
+        );
+        assert_eq!(m.to_string(), "hello");
+        assert_eq!(AsRef::<[u8]>::as_ref(&m), b"hello");
+    }
+
+    #[test]
+    fn ref_try_upgrade() {
+        let data = DashMap::new();
+        data.insert("test", 1);
+
+        let Ok(mut w_ref) = data.get("test").unwrap().try_upgrade() else {
+            panic!("no other readers")
+        };
+        *w_ref += 1;
+        assert!(data.try_get("test").is_locked());
+        drop(w_ref);
+
+        assert_eq!(*data.get("test").unwrap(), 2);
+    }
+
+    #[test]
+    fn ref_try_upgrade_contended() {
+        let data = DashMap::new();
+        data.insert("test", 1);
+
+        let other = data.get("test").unwrap();
+        let Err(r_ref) = data.get("test").unwrap().try_upgrade() else {
+            panic!("another reader holds the shard")
+        };
+        assert_eq!(*r_ref, 1);
+        drop(other);
+
+        let Ok(mut w_ref) = r_ref.try_upgrade() else {
+            panic!("the other reader is gone")
+        };
+        *w_ref.value_mut() = 3;
+        drop(w_ref);
+
+        let (l_ref, r_ref) = data.get("test").unwrap().map_split(|v| (v, v));
+        drop(r_ref);
+        let Ok(l_ref) = l_ref.try_into_single() else {
+            panic!("guard is no longer shared")
+        };
+        let Err(l_ref) = l_ref.try_upgrade() else {
+            panic!("split references can not be upgraded")
+        };
+        assert_eq!(*l_ref, 3);
+    }
+
+    #[test]
+    fn ref_into_multi() {
+        let data = DashMap::new();
+        data.insert("test", 1);
+
+        let multi = data.get("test").unwrap().into_multi();
+        let other = multi.clone();
+        assert_eq!(*other, 1);
+        assert!(data.try_get_mut("test").is_locked());
+
+        let Err(multi) = multi.try_into_single() else {
+            panic!("guard is still shared")
+        };
+        drop(other);
+        let Ok(single) = multi.try_into_single() else {
+            panic!("guard is no longer shared")
+        };
+        assert_eq!(*single, 1);
+        drop(single);
+
+        assert!(data.try_get_mut("test").is_present());
+    }
+
+    #[test]
+    fn split_with() {
+        let data = DashMap::new();
+        data.insert("test", [1, 2, 3, 4]);
+
+        let refs = data.get_mut("test").unwrap().split_with(|v| {
+            let [a, b, c, d] = v;
+            [a, b, c, d]
+        });
+        for mut r in refs {
+            *r *= 10;
+        }
+
+        let refs = data.get("test").unwrap().split_with(|v| [&v[3], &v[0]]);
+        assert!(data.try_get_mut("test").is_locked());
+        assert_eq!(refs.iter().map(|r| **r).collect::<Vec<_>>(), [40, 10]);
+        drop(refs);
+
+        assert!(data.try_get_mut("test").is_present());
+    }
+
+    #[test]
+    fn ref_cloned() {
+        let data = DashMap::new();
+        data.insert("test", (1, String::from("hello")));
+
+        let r = data.get("test").unwrap();
+        assert_eq!(r.cloned(), ("test", (1, String::from("hello"))));
+        assert_eq!(r.value_cloned(), (1, String::from("hello")));
+
+        let m = r.map(|v| &v.1);
+        assert_eq!(m.cloned(), ("test", String::from("hello")));
+        assert_eq!(m.value_cloned(), "hello");
+        assert_eq!(*m, "hello");
+    }
+
+    #[test]
+    fn formatting() {
+        let data = DashMap::new();
+        data.insert("test", "hello".to_string());
+
+        let r = data.get("test").unwrap();
+        assert_eq!(r.to_string(), "hello");
+        assert_eq!(AsRef::<[u8]>::as_ref(&r), b"hello");
+        drop(r);
+
+        let r = data.get_mut("test").unwrap();
+        assert_eq!(format!("{r:>6}"), " hello");
+        assert_eq!(AsRef::<str>::as_ref(&r), "hello");
+    }
+
+    #[test]
+    fn compare() {
+        let data = DashMap::new();
+        data.insert("a", "hello world".to_string());
+        data.insert("b", "hello".to_string());
+
+        let a_ref = data.get("a").unwrap();
+        let b_ref = data.get("b").unwrap();
+        assert_eq!(a_ref, "hello world".to_string());
+        assert_eq!(b_ref, &"hello".to_string());
+        assert_ne!(a_ref, b_ref);
+        assert!(a_ref > b_ref);
+        assert!(b_ref < "world".to_string());
+
+        let hello_ref = a_ref.map(|s| &s[..5]);
+        assert_eq!(hello_ref, "hello");
+        assert_eq!(hello_ref, *"hello");
+        assert!(hello_ref < "world");
+        assert_eq!(hello_ref, b_ref.map(|s| s.as_str()));
+    }
+
     #[test]
     fn downgrade() {
         let data = DashMap::new();
@@ -389,6 +965,66 @@ mod tests {
         };
     }
 
+    #[test]
+    fn ref_mut_replace() {
+        let data = DashMap::new();
+        data.insert("test", "old");
+        if let Some(mut w_ref) = data.get_mut("test") {
+            assert_eq!(w_ref.replace("new"), "old");
+            assert_eq!(*w_ref.key(), "test");
+            assert_eq!(*w_ref.value(), "new");
+        };
+        assert_eq!(*data.get("test").unwrap(), "new");
+    }
+
+    #[test]
+    fn ref_mut_take() {
+        let data = DashMap::new();
+        data.insert("test", vec![1, 2]);
+        if let Some(mut w_ref) = data.get_mut("test") {
+            assert_eq!(w_ref.take(), [1, 2]);
+            assert!(w_ref.is_empty());
+        };
+        assert!(data.get("test").unwrap().is_empty());
+    }
+
+    #[test]
+    fn mapped_mut_replace_take() {
+        let data = DashMap::new();
+        data.insert("test", (1, String::from("old")));
+        if let Some(mut w_ref) = data.get_mut("test").map(|r| r.map(|v| &mut v.1)) {
+            assert_eq!(w_ref.replace(String::from("new")), "old");
+            assert_eq!(w_ref.take(), "new");
+            assert_eq!(w_ref.replace(String::from("newer")), "");
+        };
+        assert_eq!(*data.get("test").unwrap(), (1, String::from("newer")));
+    }
+
+    #[test]
+    fn downgrade_map() {
+        struct Data {
+            hits: u32,
+            name: String,
+        }
+        let data = DashMap::new();
+        data.insert(
+            "test",
+            Data {
+                hits: 0,
+                name: "hello".to_string(),
+            },
+        );
+        let name_ref = data.get_mut("test").unwrap().downgrade_map(|d| {
+            d.hits += 1;
+            &d.name
+        });
+
+        std::thread::scope(|s| {
+            s.spawn(|| assert_eq!(data.get("test").unwrap().hits, 1));
+        });
+        assert_eq!(name_ref.value(), "hello");
+    }
+
     #[test]
     fn mapped_mut() {
         let data = DashMap::new();
@@ -414,6 +1050,39 @@ mod tests {
         assert_eq!(data.get("test").unwrap().value(), b"HELLO world");
     }
 
+    #[test]
+    fn mapped_mut_downgrade() {
+        let data = DashMap::new();
+        data.insert("test", *b"hello world");
+        let b_ref = data.get_mut("test").unwrap();
+        let s_ref = b_ref.try_map(|b| std::str::from_utf8_mut(b).ok()).unwrap();
+        let mut hello_ref = s_ref.try_map(|s| s.get_mut(..5)).unwrap();
+        hello_ref.make_ascii_uppercase();
+        let hello_ref = hello_ref.downgrade();
+
+        std::thread::scope(|s| {
+            s.spawn(|| assert_eq!(data.get("test").unwrap().value(), b"HELLO world"));
+        });
+        assert_eq!(hello_ref.value(), "HELLO");
+    }
+
+    #[test]
+    fn mapped_mut_map_split() {
+        let data = DashMap::new();
+        data.insert("test", b"hello world".to_vec());
+        if let Some(b_ref) = data.get_mut("test") {
+            let s_ref = b_ref.try_map(|b| std::str::from_utf8_mut(b).ok()).unwrap();
+            let (mut l_ref, mut r_ref) = s_ref.map_split(|s| s.split_at_mut(5));
+
+            std::thread::scope(|s| {
+                s.spawn(|| l_ref.make_ascii_uppercase());
+                s.spawn(|| r_ref.value_mut().make_ascii_uppercase());
+            });
+        };
+
+        assert_eq!(data.get("test").unwrap().value(), b"HELLO WORLD");
+    }
+
     #[test]
     fn mapped_ref() {
         let data = DashMap::new();
@@ -438,6 +1107,78 @@ mod tests {
         };
     }
 
+    #[test]
+    fn ref_clone_ref() {
+        let data = DashMap::new();
+        data.insert("test", "value");
+        let r_ref = data.get("test").unwrap();
+        let a_ref = r_ref.clone_ref();
+        let b_ref = r_ref.clone_ref();
+        drop(r_ref);
+
+        assert_eq!(*a_ref.value(), "value");
+        assert_eq!(*b_ref.value(), "value");
+        assert!(data.try_get_mut("test").is_locked());
+
+        drop(a_ref);
+        assert!(data.try_get_mut("test").is_locked());
+
+        drop(b_ref);
+        assert!(data.try_get_mut("test").is_present());
+    }
+
+    #[test]
+    fn ref_into_owned() {
+        let data = DashMap::new();
+        data.insert("test".to_string(), ("hello".to_string(), 1));
+        let (k, v) = data.get("test").unwrap().into_owned();
+        assert_eq!(k, "test");
+        assert_eq!(v, ("hello".to_string(), 1));
+        assert!(data.try_get_mut("test").is_present());
+
+        let (k, v) = data.get("test").unwrap().map(|v| &v.0).into_owned();
+        assert_eq!(k, "test");
+        assert_eq!(v, "hello");
+        assert!(data.try_get_mut("test").is_present());
+    }
+
+    #[test]
+    fn mapped_ref_map_split() {
+        let data = DashMap::new();
+        data.insert("test", *b"hello world");
+        if let Some(b_ref) = data.get("test") {
+            let s_ref = b_ref.try_map(|b| std::str::from_utf8(b).ok()).unwrap();
+            let (l_ref, r_ref) = s_ref.map_split(|s| s.split_at(5));
+
+            std::thread::scope(|s| {
+                s.spawn(|| assert_eq!(l_ref.value(), "hello"));
+                s.spawn(|| assert_eq!(r_ref.value(), " world"));
+            });
+
+            drop(l_ref);
+            assert!(data.try_get_mut("test").is_locked());
+            drop(r_ref);
+            assert!(data.try_get_mut("test").is_present());
+        };
+    }
+
+    #[test]
+    fn mapped_ref_try_map_split() {
+        let data = DashMap::new();
+        data.insert("test", *b"key=value");
+        if let Some(b_ref) = data.get("test") {
+            let s_ref = b_ref.try_map(|b| std::str::from_utf8(b).ok()).unwrap();
+            let Err(s_ref) = s_ref.try_map_split(|s| s.split_once(':')) else {
+                panic!("value does not contain ':'")
+            };
+            assert_eq!(s_ref.value(), "key=value");
+
+            let (l_ref, r_ref) = s_ref.try_map_split(|s| s.split_once('=')).unwrap();
+            assert_eq!(l_ref.value(), "key");
+            assert_eq!(r_ref.value(), "value");
+        };
+    }
+
     #[test]
     fn ref_mut_map_split() {
         let data = DashMap::new();
@@ -453,6 +1194,99 @@ mod tests {
         assert_eq!(b_ref.value(), "HELLO world");
     }
 
+    #[test]
+    fn ref_mut_map_mut_and_ref() {
+        struct Data(String, String);
+        let data = DashMap::new();
+        data.insert("test", Data("hello".to_string(), "world".to_string()));
+        if let Some(b_ref) = data.get_mut("test") {
+            let (mut l_ref, r_ref) = b_ref.map_mut_and_ref(|d| (&mut d.0, &d.1));
+            l_ref.push_str(r_ref.value());
+            drop(l_ref);
+
+            assert_eq!(r_ref.value(), "world");
+            assert!(data.try_get("test").is_locked());
+        };
+        assert_eq!(data.get("test").unwrap().0, "helloworld");
+    }
+
+    #[test]
+    fn ref_try_map_split() {
+        enum Data {
+            Pair(String, String),
+            Single(String),
+        }
+        let data = DashMap::new();
+        data.insert("pair", Data::Pair("hello".to_string(), "world".to_string()));
+        data.insert("single", Data::Single("hello".to_string()));
+
+        fn split(d: &Data) -> Option<(&String, &String)> {
+            match d {
+                Data::Pair(l, r) => Some((l, r)),
+                Data::Single(_) => None,
+            }
+        }
+
+        let Err(s_ref) = data.get("single").unwrap().try_map_split(split) else {
+            panic!("single can not be split")
+        };
+        assert!(matches!(s_ref.value(), Data::Single(s) if s == "hello"));
+
+        let Ok((l_ref, r_ref)) = data.get("pair").unwrap().try_map_split(split) else {
+            panic!("pair can be split")
+        };
+        assert_eq!(l_ref.value(), "hello");
+        assert_eq!(r_ref.value(), "world");
+    }
+
+    #[test]
+    fn ref_mut_try_map_split() {
+        let data = DashMap::new();
+        data.insert("test", "hello world".to_string());
+
+        let Err(mut w_ref) = data
+            .get_mut("test")
+            .unwrap()
+            .try_map_split(|s| {
+                let i = s.find('!')?;
+                Some(s.split_at_mut(i))
+            })
+        else {
+            panic!("value does not contain '!'")
+        };
+        w_ref.push('!');
+
+        let Ok((mut l_ref, r_ref)) = w_ref.try_map_split(|s| {
+            let i = s.find(' ')?;
+            Some(s.split_at_mut(i))
+        }) else {
+            panic!("value contains ' '")
+        };
+        l_ref.make_ascii_uppercase();
+        assert_eq!(r_ref.value(), " world!");
+        drop((l_ref, r_ref));
+
+        assert_eq!(*data.get("test").unwrap(), "HELLO world!");
+    }
+
+    #[test]
+    fn map_split_at() {
+        let data = DashMap::new();
+        data.insert("test", vec![1, 2, 3]);
+
+        let (mut a, b) = data.get_mut("test").unwrap().map_split_at(3);
+        assert!(b.is_empty());
+        a.reverse();
+        drop((a, b));
+        assert_eq!(*data.get("test").unwrap(), [3, 2, 1]);
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            data.get_mut("test").unwrap().map_split_at(4).0.len()
+        }));
+        assert!(res.is_err());
+        assert!(data.try_get_mut("test").is_present());
+    }
+
     #[test]
     fn mapped_ref_again() {
         let data = DashMap::new();
@@ -0,0 +1,610 @@
+use crate::lock::{RwLockReadGuardDetached, RwLockWriteGuardDetached};
+use crate::{DashMap, HashMap};
+use core::hash::{BuildHasher, Hash};
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+use std::collections::hash_map::RandomState;
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+
+/// A read guard into a [`DashMap`] held through an [`Arc`].
+///
+/// Unlike [`Ref`](super::one::Ref), this keeps the map alive itself instead of borrowing it,
+/// so it can be returned from functions or moved into other threads and tasks.
+pub struct OwnedRef<K, V, S = RandomState> {
+    // Fields are dropped in declaration order: the shard must be unlocked before the map
+    // owning it can be freed.
+    _guard: RwLockReadGuardDetached<'static>,
+    k: *const K,
+    v: *const V,
+    _map: Arc<DashMap<K, V, S>>,
+}
+
+// SAFETY: An `OwnedRef` hands out `&K` and `&V` and may drop the last handle to the map.
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Send for OwnedRef<K, V, S> {}
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Sync for OwnedRef<K, V, S> {}
+
+impl<K, V, S> OwnedRef<K, V, S> {
+    /// # Safety
+    ///
+    /// `guard` must be a read lock on the shard of `map` that contains `k` and `v`.
+    pub(crate) unsafe fn new(
+        map: Arc<DashMap<K, V, S>>,
+        guard: RwLockReadGuardDetached<'_>,
+        k: &K,
+        v: &V,
+    ) -> Self {
+        Self {
+            // SAFETY: The lock lives inside `map`, which is kept alive for as long as the guard.
+            _guard: unsafe {
+                mem::transmute::<RwLockReadGuardDetached<'_>, RwLockReadGuardDetached<'static>>(
+                    guard,
+                )
+            },
+            k,
+            v,
+            _map: map,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        self.pair().0
+    }
+
+    pub fn value(&self) -> &V {
+        self.pair().1
+    }
+
+    pub fn pair(&self) -> (&K, &V) {
+        // SAFETY: The shard containing the entry is read-locked by `_guard`.
+        unsafe { (&*self.k, &*self.v) }
+    }
+
+    pub fn map<F, T: ?Sized>(self, f: F) -> OwnedMappedRef<K, V, T, S>
+    where
+        F: FnOnce(&V) -> &T,
+    {
+        // SAFETY: The shard containing the entry is read-locked by `_guard`.
+        let v = f(unsafe { &*self.v });
+        OwnedMappedRef {
+            _guard: self._guard,
+            k: self.k,
+            v,
+            _map: self._map,
+        }
+    }
+
+    pub fn try_map<F, T: ?Sized>(self, f: F) -> Result<OwnedMappedRef<K, V, T, S>, Self>
+    where
+        F: FnOnce(&V) -> Option<&T>,
+    {
+        // SAFETY: The shard containing the entry is read-locked by `_guard`.
+        if let Some(v) = f(unsafe { &*self.v }) {
+            Ok(OwnedMappedRef {
+                _guard: self._guard,
+                k: self.k,
+                v,
+                _map: self._map,
+            })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<K: Debug, V: Debug, S> Debug for OwnedRef<K, V, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OwnedRef")
+            .field("k", self.key())
+            .field("v", self.value())
+            .finish()
+    }
+}
+
+impl<K, V, S> Deref for OwnedRef<K, V, S> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value()
+    }
+}
+
+/// An [`OwnedRef`] narrowed down to a part of the value.
+pub struct OwnedMappedRef<K, V, T: ?Sized, S = RandomState> {
+    // Must be dropped before `_map`, see `OwnedRef`.
+    _guard: RwLockReadGuardDetached<'static>,
+    k: *const K,
+    v: *const T,
+    _map: Arc<DashMap<K, V, S>>,
+}
+
+// SAFETY: Same as for `OwnedRef`, with `&T` handed out instead of `&V`.
+unsafe impl<K: Send + Sync, V: Send + Sync, T: Sync + ?Sized, S: Send + Sync> Send
+    for OwnedMappedRef<K, V, T, S>
+{
+}
+unsafe impl<K: Send + Sync, V: Send + Sync, T: Sync + ?Sized, S: Send + Sync> Sync
+    for OwnedMappedRef<K, V, T, S>
+{
+}
+
+impl<K, V, T: ?Sized, S> OwnedMappedRef<K, V, T, S> {
+    pub fn key(&self) -> &K {
+        self.pair().0
+    }
+
+    pub fn value(&self) -> &T {
+        self.pair().1
+    }
+
+    pub fn pair(&self) -> (&K, &T) {
+        // SAFETY: The shard containing the entry is read-locked by `_guard`.
+        unsafe { (&*self.k, &*self.v) }
+    }
+
+    pub fn map<F, T2: ?Sized>(self, f: F) -> OwnedMappedRef<K, V, T2, S>
+    where
+        F: FnOnce(&T) -> &T2,
+    {
+        // SAFETY: The shard containing the entry is read-locked by `_guard`.
+        let v = f(unsafe { &*self.v });
+        OwnedMappedRef {
+            _guard: self._guard,
+            k: self.k,
+            v,
+            _map: self._map,
+        }
+    }
+
+    pub fn try_map<F, T2: ?Sized>(self, f: F) -> Result<OwnedMappedRef<K, V, T2, S>, Self>
+    where
+        F: FnOnce(&T) -> Option<&T2>,
+    {
+        // SAFETY: The shard containing the entry is read-locked by `_guard`.
+        if let Some(v) = f(unsafe { &*self.v }) {
+            Ok(OwnedMappedRef {
+                _guard: self._guard,
+                k: self.k,
+                v,
+                _map: self._map,
+            })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<K: Debug, V, T: Debug + ?Sized, S> Debug for OwnedMappedRef<K, V, T, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OwnedMappedRef")
+            .field("k", self.key())
+            .field("v", &self.value())
+            .finish()
+    }
+}
+
+impl<K, V, T: ?Sized, S> Deref for OwnedMappedRef<K, V, T, S> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value()
+    }
+}
+
+/// A write guard into a [`DashMap`] held through an [`Arc`].
+///
+/// This is the owned counterpart of [`RefMut`](super::one::RefMut). The shard stays write-locked
+/// for as long as the guard lives, so holding it across an `.await` point blocks every other
+/// task and thread that touches the same shard until the awaiting task resumes.
+pub struct OwnedRefMut<K, V, S = RandomState> {
+    // Must be dropped before `_map`, see `OwnedRef`.
+    guard: RwLockWriteGuardDetached<'static>,
+    k: *const K,
+    v: *mut V,
+    _map: Arc<DashMap<K, V, S>>,
+}
+
+// SAFETY: An `OwnedRefMut` hands out `&K` and `&mut V` and may drop the last handle to the map.
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Send for OwnedRefMut<K, V, S> {}
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Sync for OwnedRefMut<K, V, S> {}
+
+impl<K, V, S> OwnedRefMut<K, V, S> {
+    /// # Safety
+    ///
+    /// `guard` must be a write lock on the shard of `map` that contains `k` and `v`.
+    pub(crate) unsafe fn new(
+        map: Arc<DashMap<K, V, S>>,
+        guard: RwLockWriteGuardDetached<'_>,
+        k: &K,
+        v: &mut V,
+    ) -> Self {
+        Self {
+            // SAFETY: The lock lives inside `map`, which is kept alive for as long as the guard.
+            guard: unsafe {
+                mem::transmute::<RwLockWriteGuardDetached<'_>, RwLockWriteGuardDetached<'static>>(
+                    guard,
+                )
+            },
+            k,
+            v,
+            _map: map,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        self.pair().0
+    }
+
+    pub fn value(&self) -> &V {
+        self.pair().1
+    }
+
+    pub fn value_mut(&mut self) -> &mut V {
+        self.pair_mut().1
+    }
+
+    pub fn pair(&self) -> (&K, &V) {
+        // SAFETY: The shard containing the entry is write-locked by `guard`.
+        unsafe { (&*self.k, &*self.v) }
+    }
+
+    pub fn pair_mut(&mut self) -> (&K, &mut V) {
+        // SAFETY: The shard containing the entry is write-locked by `guard`.
+        unsafe { (&*self.k, &mut *self.v) }
+    }
+
+    pub fn downgrade(self) -> OwnedRef<K, V, S> {
+        // SAFETY: No mutable reference to the value can outlive `self`.
+        let guard = unsafe { self.guard.downgrade() };
+        // SAFETY: `guard` still locks the shard containing the entry.
+        unsafe { OwnedRef::new(self._map, guard, &*self.k, &*self.v) }
+    }
+}
+
+impl<K: Debug, V: Debug, S> Debug for OwnedRefMut<K, V, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OwnedRefMut")
+            .field("k", self.key())
+            .field("v", self.value())
+            .finish()
+    }
+}
+
+impl<K, V, S> Deref for OwnedRefMut<K, V, S> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value()
+    }
+}
+
+impl<K, V, S> DerefMut for OwnedRefMut<K, V, S> {
+    fn deref_mut(&mut self) -> &mut V {
+        self.value_mut()
+    }
+}
+
+/// An entry of a [`DashMap`] held through an [`Arc`], see [`DashMap::entry_owned`].
+///
+/// Like [`OwnedRefMut`], this keeps the shard write-locked until it is dropped.
+pub enum OwnedEntry<K, V, S = RandomState> {
+    Occupied(OwnedOccupiedEntry<K, V, S>),
+    Vacant(OwnedVacantEntry<K, V, S>),
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Clone> OwnedEntry<K, V, S> {
+    /// Apply a function to the stored value if it exists.
+    pub fn and_modify(self, f: impl FnOnce(&mut V)) -> Self {
+        match self {
+            OwnedEntry::Occupied(mut entry) => {
+                f(entry.get_mut());
+
+                OwnedEntry::Occupied(entry)
+            }
+
+            OwnedEntry::Vacant(entry) => OwnedEntry::Vacant(entry),
+        }
+    }
+
+    /// Get the key of the entry.
+    pub fn key(&self) -> &K {
+        match *self {
+            OwnedEntry::Occupied(ref entry) => entry.key(),
+            OwnedEntry::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise insert the default and return a mutable reference to that.
+    pub fn or_default(self) -> OwnedRefMut<K, V, S>
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise a provided value and return a mutable reference to that.
+    pub fn or_insert(self, value: V) -> OwnedRefMut<K, V, S> {
+        self.or_insert_with(|| value)
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise insert the result of a provided function and return a mutable reference to that.
+    pub fn or_insert_with(self, value: impl FnOnce() -> V) -> OwnedRefMut<K, V, S> {
+        match self {
+            OwnedEntry::Occupied(entry) => entry.into_ref(),
+            OwnedEntry::Vacant(entry) => entry.insert(value()),
+        }
+    }
+}
+
+pub struct OwnedOccupiedEntry<K, V, S = RandomState> {
+    // Must be dropped before `map`, see `OwnedRef`.
+    guard: RwLockWriteGuardDetached<'static>,
+    key: K,
+    hash: u64,
+    shard: *mut HashMap<K, V>,
+    slot: *mut (K, V),
+    map: Arc<DashMap<K, V, S>>,
+}
+
+// SAFETY: Same as for `OwnedRefMut`, additionally owning a `K`.
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Send for OwnedOccupiedEntry<K, V, S> {}
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Sync for OwnedOccupiedEntry<K, V, S> {}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Clone> OwnedOccupiedEntry<K, V, S> {
+    /// # Safety
+    ///
+    /// `guard` must be a write lock on `shard`, which must belong to `map` and contain `slot`.
+    /// `hash` must be the hash of `key`.
+    pub(crate) unsafe fn new(
+        map: Arc<DashMap<K, V, S>>,
+        guard: RwLockWriteGuardDetached<'_>,
+        key: K,
+        hash: u64,
+        shard: *mut HashMap<K, V>,
+        slot: *mut (K, V),
+    ) -> Self {
+        Self {
+            // SAFETY: The lock lives inside `map`, which is kept alive for as long as the guard.
+            guard: unsafe {
+                mem::transmute::<RwLockWriteGuardDetached<'_>, RwLockWriteGuardDetached<'static>>(
+                    guard,
+                )
+            },
+            key,
+            hash,
+            shard,
+            slot,
+            map,
+        }
+    }
+
+    pub fn get(&self) -> &V {
+        // SAFETY: The shard containing the entry is write-locked by `guard`.
+        unsafe { &(*self.slot).1 }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        // SAFETY: The shard containing the entry is write-locked by `guard`.
+        unsafe { &mut (*self.slot).1 }
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn into_ref(self) -> OwnedRefMut<K, V, S> {
+        // SAFETY: The shard containing the entry is write-locked by `guard`.
+        let (k, v) = unsafe { &mut *self.slot };
+        // SAFETY: `guard` locks the shard containing the entry.
+        unsafe { OwnedRefMut::new(self.map, self.guard, k, v) }
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn key(&self) -> &K {
+        // SAFETY: The shard containing the entry is write-locked by `guard`.
+        unsafe { &(*self.slot).0 }
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        // SAFETY: The shard is write-locked by `guard` and still contains `slot`.
+        let shard = unsafe { &mut *self.shard };
+        match shard.find_entry(self.hash, |entry| ptr::eq(entry, self.slot)) {
+            Ok(entry) => entry.remove().0,
+            Err(_) => unreachable!("locked entry vanished from its shard"),
+        }
+    }
+}
+
+pub struct OwnedVacantEntry<K, V, S = RandomState> {
+    // Must be dropped before `map`, see `OwnedRef`.
+    guard: RwLockWriteGuardDetached<'static>,
+    key: K,
+    hash: u64,
+    shard: *mut HashMap<K, V>,
+    map: Arc<DashMap<K, V, S>>,
+}
+
+// SAFETY: Same as for `OwnedOccupiedEntry`.
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Send for OwnedVacantEntry<K, V, S> {}
+unsafe impl<K: Send + Sync, V: Send + Sync, S: Send + Sync> Sync for OwnedVacantEntry<K, V, S> {}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Clone> OwnedVacantEntry<K, V, S> {
+    /// # Safety
+    ///
+    /// `guard` must be a write lock on `shard`, which must belong to `map`.
+    /// `hash` must be the hash of `key`.
+    pub(crate) unsafe fn new(
+        map: Arc<DashMap<K, V, S>>,
+        guard: RwLockWriteGuardDetached<'_>,
+        key: K,
+        hash: u64,
+        shard: *mut HashMap<K, V>,
+    ) -> Self {
+        Self {
+            // SAFETY: The lock lives inside `map`, which is kept alive for as long as the guard.
+            guard: unsafe {
+                mem::transmute::<RwLockWriteGuardDetached<'_>, RwLockWriteGuardDetached<'static>>(
+                    guard,
+                )
+            },
+            key,
+            hash,
+            shard,
+            map,
+        }
+    }
+
+    pub fn insert(self, value: V) -> OwnedRefMut<K, V, S> {
+        // SAFETY: The shard is write-locked by `guard`.
+        let shard = unsafe { &mut *self.shard };
+        let map = &self.map;
+        let (k, v) = shard
+            .insert_unique(self.hash, (self.key, value), |(k, _v)| map.hash_u64(k))
+            .into_mut();
+        // SAFETY: `guard` locks the shard the entry was inserted into.
+        unsafe { OwnedRefMut::new(self.map, self.guard, k, v) }
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DashMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn owned_ref_outlives_borrow() {
+        fn lookup(map: &Arc<DashMap<u32, String>>) -> super::OwnedRef<u32, String> {
+            map.get_owned(&1).unwrap()
+        }
+
+        let map = Arc::new(DashMap::new());
+        map.insert(1, "hello".to_string());
+
+        let r = lookup(&map);
+        assert_eq!(r.pair(), (&1, &"hello".to_string()));
+        assert!(map.try_get_mut(&1).is_locked());
+        drop(r);
+        assert!(map.try_get_mut(&1).is_present());
+        assert!(map.get_owned(&2).is_none());
+    }
+
+    #[test]
+    fn owned_ref_in_thread() {
+        let map = Arc::new(DashMap::new());
+        map.insert("test", "hello world".to_string());
+
+        let r = map.get_owned("test").unwrap();
+        // The thread may now hold the last handle to the map.
+        drop(map);
+
+        let len = std::thread::spawn(move || {
+            let word = r.map(|s| &s[..5]);
+            assert_eq!(&*word, "hello");
+            assert_eq!(*word.key(), "test");
+            word.len()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(len, 5);
+    }
+
+    #[test]
+    fn owned_ref_try_map() {
+        let map = Arc::new(DashMap::new());
+        map.insert(1, vec![1, 2, 3]);
+
+        let Err(r) = map.get_owned(&1).unwrap().try_map(|v| v.get(3)) else {
+            panic!("index 3 is out of bounds")
+        };
+        let Ok(r) = r.try_map(|v| v.get(2)) else {
+            panic!("index 2 is in bounds")
+        };
+        assert_eq!(*r, 3);
+        assert_eq!(format!("{r:?}"), "OwnedMappedRef { k: 1, v: 3 }");
+    }
+
+    #[test]
+    fn owned_ref_mut_in_thread() {
+        let map = Arc::new(DashMap::new());
+        map.insert("session", 1);
+
+        let mut r = map.get_mut_owned("session").unwrap();
+        std::thread::spawn(move || {
+            *r += 1;
+            *r.value_mut() += 1;
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(*map.get("session").unwrap(), 3);
+        assert!(map.get_mut_owned("other").is_none());
+    }
+
+    #[test]
+    fn owned_ref_mut_downgrade() {
+        let map = Arc::new(DashMap::new());
+        map.insert(1, 1);
+
+        let mut w = map.get_mut_owned(&1).unwrap();
+        *w.pair_mut().1 = 2;
+        assert!(map.try_get(&1).is_locked());
+
+        let r = w.downgrade();
+        assert_eq!(*r, 2);
+        assert!(map.try_get(&1).is_present());
+        assert!(map.try_get_mut(&1).is_locked());
+    }
+
+    #[test]
+    fn owned_entry() {
+        let map = Arc::new(DashMap::new());
+
+        let handle = map.entry_owned("a").or_insert(1);
+        let handle = std::thread::spawn(move || {
+            let mut handle = handle;
+            *handle += 1;
+            handle.downgrade()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(*handle, 2);
+        drop(handle);
+
+        *map.entry_owned("a").and_modify(|v| *v *= 10).or_default() += 1;
+        assert_eq!(*map.get("a").unwrap(), 21);
+
+        let super::OwnedEntry::Occupied(entry) = map.entry_owned("a") else {
+            panic!("entry was inserted")
+        };
+        assert_eq!(entry.remove_entry(), ("a", 21));
+        assert!(map.is_empty());
+
+        let super::OwnedEntry::Vacant(entry) = map.entry_owned("b") else {
+            panic!("entry does not exist")
+        };
+        assert_eq!(*entry.key(), "b");
+        assert_eq!(entry.into_key(), "b");
+        assert!(map.try_entry("b").is_some());
+    }
+}
@@ -4,9 +4,13 @@ use crate::{DashMap, HashMap};
 use core::hash::{BuildHasher, Hash};
 use crossbeam_utils::CachePadded;
 use rayon::iter::plumbing::UnindexedConsumer;
-use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+use rayon::iter::{
+    FromParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelExtend,
+    ParallelIterator,
+};
 use std::sync::Arc;
 
+/// Inserts the items from the rayon thread pool, see the implementation for `&DashMap`.
 impl<K, V, S> ParallelExtend<(K, V)> for DashMap<K, V, S>
 where
     K: Send + Sync + Eq + Hash,
@@ -23,6 +27,11 @@ where
 
 // Since we don't actually need mutability, we can implement this on a
 // reference, similar to `io::Write for &File`.
+/// Inserts the items from the rayon thread pool. Every item only locks the shard it hashes to,
+/// so workers rarely contend.
+///
+/// If the iterator yields a key more than once, which of its values ends up in the map depends
+/// on how rayon schedules the workers.
 impl<K, V, S> ParallelExtend<(K, V)> for &'_ DashMap<K, V, S>
 where
     K: Send + Sync + Eq + Hash,
@@ -40,6 +49,10 @@ where
     }
 }
 
+/// Collects the items from the rayon thread pool.
+///
+/// If the iterator yields a key more than once, which of its values ends up in the map depends
+/// on how rayon schedules the workers.
 impl<K, V, S> FromParallelIterator<(K, V)> for DashMap<K, V, S>
 where
     K: Send + Sync + Eq + Hash,
@@ -176,6 +189,36 @@ where
             shards: &self.shards,
         }
     }
+
+    /// Parallel version of [`retain`](DashMap::retain): the shards are write-locked and
+    /// filtered independently on the rayon thread pool.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn par_retain(&self, f: impl Fn(&K, &mut V) -> bool + Sync) {
+        self.shards.par_iter().for_each(|s| {
+            s.write().retain(|(k, v)| f(k, v));
+        });
+    }
+
+    /// Parallel version of [`fold`](DashMap::fold): each shard is read-locked and folded
+    /// into its own accumulator, starting from `identity()`, and the results are then
+    /// merged with `combine`. Like `fold`, this is not atomic across shards.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn par_fold<A: Send>(
+        &self,
+        identity: impl Fn() -> A + Sync + Send,
+        fold: impl Fn(A, &K, &V) -> A + Sync + Send,
+        combine: impl Fn(A, A) -> A + Sync + Send,
+    ) -> A {
+        self.shards
+            .par_iter()
+            .map(|s| {
+                let shard = s.read();
+                shard.iter().fold(identity(), |acc, (k, v)| fold(acc, k, v))
+            })
+            .reduce(&identity, combine)
+    }
 }
 
 pub struct IterMut<'a, K, V> {
@@ -210,3 +253,57 @@ where
             .drive_unindexed(consumer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::DashMap;
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    #[test]
+    fn par_retain_matches_retain() {
+        // A fixed xorshift scramble keeps the predicate arbitrary but reproducible.
+        fn keep(k: &u64, v: &mut u64) -> bool {
+            let mut x = k ^ 0x9e37_79b9_7f4a_7c15;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            *v += 1;
+            x % 3 != 0
+        }
+
+        let serial: DashMap<u64, u64> = (0..10_000).into_par_iter().map(|i| (i, i)).collect();
+        let parallel: DashMap<u64, u64> = (0..10_000).into_par_iter().map(|i| (i, i)).collect();
+
+        serial.retain(keep);
+        parallel.par_retain(keep);
+
+        assert!(!serial.is_empty());
+        assert!(serial.len() < 10_000);
+        assert_eq!(serial, parallel);
+    }
+
+    #[test]
+    fn par_fold_matches_fold() {
+        let map: DashMap<u64, u64> = (0..10_000).into_par_iter().map(|i| (i, i * 3)).collect();
+
+        let serial = map.fold(0, |acc, k, v| acc + k * v);
+        let parallel = map.par_fold(|| 0, |acc, k, v| acc + k * v, |a, b| a + b);
+        assert_eq!(serial, parallel);
+        assert_eq!(
+            map.par_fold(Vec::<u8>::new, |acc, _, _| acc, |a, _| a),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn from_par_iter_large() {
+        const N: u64 = 10_000;
+
+        let map: DashMap<u64, u64> = (0..N).into_par_iter().map(|i| (i, i * 2)).collect();
+
+        assert_eq!(map.len(), N as usize);
+        assert!((0..N)
+            .into_par_iter()
+            .all(|i| *map.get(&i).unwrap() == i * 2));
+    }
+}
@@ -6,6 +6,15 @@ use serde::de::{Deserialize, MapAccess, SeqAccess, Visitor};
 use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 use serde::Deserializer;
 
+/// Caps the capacity reserved from a size hint, which comes from untrusted input,
+/// to about one megabyte. Larger inputs still grow the map as they are read.
+fn cautious<T>(hint: Option<usize>) -> usize {
+    const MAX_PREALLOC_BYTES: usize = 1024 * 1024;
+
+    hint.unwrap_or(0)
+        .min(MAX_PREALLOC_BYTES / core::mem::size_of::<T>().max(1))
+}
+
 pub struct DashMapVisitor<K, V, S> {
     marker: PhantomData<fn() -> DashMap<K, V, S>>,
 }
@@ -38,8 +47,10 @@ where
     where
         M: MapAccess<'de>,
     {
-        let map =
-            DashMap::with_capacity_and_hasher(access.size_hint().unwrap_or(0), Default::default());
+        let map = DashMap::with_capacity_and_hasher(
+            cautious::<(K, V)>(access.size_hint()),
+            Default::default(),
+        );
 
         while let Some((key, value)) = access.next_entry()? {
             map.insert(key, value);
@@ -114,8 +125,10 @@ where
     where
         M: SeqAccess<'de>,
     {
-        let map =
-            DashSet::with_capacity_and_hasher(access.size_hint().unwrap_or(0), Default::default());
+        let map = DashSet::with_capacity_and_hasher(
+            cautious::<K>(access.size_hint()),
+            Default::default(),
+        );
 
         while let Some(key) = access.next_element()? {
             map.insert(key);
@@ -169,35 +182,130 @@ macro_rules! serialize_impl {
 }
 
 // Map
-impl<'a, K: Eq + Hash, V: Serialize> Serialize for mapref::multiple::RefMulti<'a, K, V> {
+impl<'a, K, V: Serialize> Serialize for mapref::multiple::RefMulti<'a, K, V> {
+    serialize_impl! {}
+}
+
+impl<'a, K, V: Serialize> Serialize for mapref::multiple::RefMutMulti<'a, K, V> {
+    serialize_impl! {}
+}
+
+impl<'a, K, V: Serialize> Serialize for mapref::one::Ref<'a, K, V> {
     serialize_impl! {}
 }
 
-impl<'a, K: Eq + Hash, V: Serialize> Serialize for mapref::multiple::RefMutMulti<'a, K, V> {
+impl<'a, K, V: Serialize> Serialize for mapref::one::RefMut<'a, K, V> {
     serialize_impl! {}
 }
 
-impl<'a, K: Eq + Hash, V: Serialize> Serialize for mapref::one::Ref<'a, K, V> {
+impl<'a, K, T: Serialize> Serialize for mapref::one::MappedRef<'a, K, T> {
     serialize_impl! {}
 }
 
-impl<'a, K: Eq + Hash, V: Serialize> Serialize for mapref::one::RefMut<'a, K, V> {
+impl<'a, K, T: Serialize> Serialize for mapref::one::MappedRefMut<'a, K, T> {
     serialize_impl! {}
 }
 
-impl<'a, K: Eq + Hash, T: Serialize> Serialize for mapref::one::MappedRef<'a, K, T> {
+impl<'a, K, T: Serialize> Serialize for mapref::multiple::MappedRefMulti<'a, K, T> {
     serialize_impl! {}
 }
 
-impl<'a, K: Eq + Hash, T: Serialize> Serialize for mapref::one::MappedRefMut<'a, K, T> {
+impl<'a, K, T: Serialize> Serialize for mapref::multiple::MappedRefMutMulti<'a, K, T> {
     serialize_impl! {}
 }
 
 // Set
-impl<'a, V: Hash + Eq + Serialize> Serialize for setref::multiple::RefMulti<'a, V> {
+impl<'a, V: Serialize> Serialize for setref::multiple::RefMulti<'a, V> {
     serialize_impl! {}
 }
 
-impl<'a, V: Hash + Eq + Serialize> Serialize for setref::one::Ref<'a, V> {
+impl<'a, V: Serialize> Serialize for setref::one::Ref<'a, V> {
     serialize_impl! {}
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{DashMap, DashSet};
+    use serde_test::{assert_ser_tokens, assert_tokens, Token};
+
+    #[test]
+    fn map_round_trip() {
+        let map = DashMap::new();
+        map.insert(1, 2);
+
+        assert_tokens(
+            &map,
+            &[
+                Token::Map { len: Some(1) },
+                Token::I32(1),
+                Token::I32(2),
+                Token::MapEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn set_round_trip() {
+        let set = DashSet::new();
+        set.insert(1);
+
+        assert_tokens(
+            &set,
+            &[Token::Seq { len: Some(1) }, Token::I32(1), Token::SeqEnd],
+        );
+    }
+
+    #[test]
+    fn serialize_guards() {
+        let map = DashMap::new();
+        map.insert(1, (2, 3));
+
+        assert_ser_tokens(&map.get(&1).unwrap().map(|v| &v.0), &[Token::I32(2)]);
+        assert_ser_tokens(
+            &map.get_mut(&1).unwrap().map(|v| &mut v.1),
+            &[Token::I32(3)],
+        );
+
+        let r = map.iter().next().unwrap();
+        assert_ser_tokens(&r.map(|v| &v.0), &[Token::I32(2)]);
+        let r = map.iter_mut().next().unwrap();
+        assert_ser_tokens(&r.map(|v| &mut v.1), &[Token::I32(3)]);
+
+        let set = DashSet::new();
+        set.insert(1);
+        assert_ser_tokens(&set.get(&1).unwrap(), &[Token::I32(1)]);
+        assert_ser_tokens(&set.iter().next().unwrap(), &[Token::I32(1)]);
+    }
+
+    /// Yields its items while claiming to hold `usize::MAX` of them.
+    struct Lying<I>(I);
+
+    impl<I: Iterator> Iterator for Lying<I> {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.0.next()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, Some(usize::MAX))
+        }
+    }
+
+    #[test]
+    fn huge_declared_length() {
+        use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+        use serde::Deserialize;
+
+        let entries = Lying((0..3).map(|i| (i, i)));
+        let map = DashMap::<i32, i32>::deserialize(MapDeserializer::<_, Error>::new(entries));
+        let map = map.unwrap();
+        assert_eq!(map.len(), 3);
+        assert!(map.capacity() <= 2 * 1024 * 1024 / core::mem::size_of::<(i32, i32)>());
+
+        let set = DashSet::<i32>::deserialize(SeqDeserializer::<_, Error>::new(Lying(0..3)));
+        let set = set.unwrap();
+        assert_eq!(set.len(), 3);
+        assert!(set.capacity() <= 2 * 1024 * 1024 / core::mem::size_of::<i32>());
+    }
+}
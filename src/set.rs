@@ -1,4 +1,4 @@
-use crate::iter_set::{Iter, OwningIter};
+use crate::iter_set::{Drain, Iter, OwningIter};
 #[cfg(feature = "raw-api")]
 use crate::lock::RwLock;
 use crate::setref::one::Ref;
@@ -287,6 +287,71 @@ impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
         self.inner.get(key).map(Ref::new)
     }
 
+    /// Get a reference to the element in the set equal to `key`, inserting `key` first if there is none.
+    /// The returned reference always points at the element stored in the set, which makes this
+    /// useful for interning. If the key is already present, the shard is only read-locked.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    /// use std::sync::Arc;
+    ///
+    /// let strings: DashSet<Arc<str>> = DashSet::new();
+    /// let first = strings.get_or_insert(Arc::from("hello")).clone();
+    /// let second = strings.get_or_insert(Arc::from("hello")).clone();
+    /// assert!(Arc::ptr_eq(&first, &second));
+    /// ```
+    pub fn get_or_insert(&'a self, key: K) -> Ref<'a, K> {
+        Ref::new(self.inner.get_or_insert(key, ()))
+    }
+
+    /// Inserts a key into the set only if no equal key is present yet.
+    /// Returns None if the key was inserted, or gives the key back otherwise.
+    ///
+    /// This is like [`insert`](DashSet::insert), but the caller gets the rejected key back.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    ///
+    /// let claimed = DashSet::new();
+    /// assert_eq!(claimed.insert_if_absent("job-1"), None);
+    /// assert_eq!(claimed.insert_if_absent("job-1"), Some("job-1"));
+    /// ```
+    pub fn insert_if_absent(&self, key: K) -> Option<K> {
+        self.inner
+            .try_insert(key, ())
+            .err()
+            .map(|err| err.entry.into_key())
+    }
+
+    /// Inserts a key into the set only if no equal key is present yet,
+    /// and returns a reference to the inserted key. Otherwise the rejected key is handed back.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    ///
+    /// let claimed = DashSet::new();
+    /// assert!(claimed.try_insert("job-1").is_ok());
+    /// assert_eq!(claimed.try_insert("job-1").err(), Some("job-1"));
+    /// ```
+    pub fn try_insert(&'a self, key: K) -> Result<Ref<'a, K>, K> {
+        match self.inner.try_insert(key, ()) {
+            Ok(r) => Ok(Ref::new(r.downgrade())),
+            Err(err) => Err(err.entry.into_key()),
+        }
+    }
+
     /// Remove excess capacity to reduce memory usage.
     pub fn shrink_to_fit(&self) {
         self.inner.shrink_to_fit()
@@ -311,6 +376,26 @@ impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
         self.inner.retain(|k, _| f(k))
     }
 
+    /// Like [`retain`](DashSet::retain), but returns the removed keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    ///
+    /// let people = DashSet::new();
+    /// people.insert("Albin");
+    /// people.insert("Jones");
+    /// assert_eq!(people.retain_collect(|name| name.contains('i')), ["Jones"]);
+    /// ```
+    pub fn retain_collect(&self, mut f: impl FnMut(&K) -> bool) -> Vec<K> {
+        self.inner
+            .retain_collect(|k, _| f(k))
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect()
+    }
+
     /// Fetches the total number of keys stored in the set.
     ///
     /// # Examples
@@ -359,6 +444,43 @@ impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
         self.inner.clear()
     }
 
+    /// Removes all keys from the set, and calls `f` with each of them.
+    /// See [`DashMap::clear_with`] for how the set is locked meanwhile.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    ///
+    /// let people = DashSet::new();
+    /// people.insert("Albin");
+    ///
+    /// let mut removed = Vec::new();
+    /// people.clear_with(|name| removed.push(name));
+    /// assert_eq!(removed, ["Albin"]);
+    /// assert!(people.is_empty());
+    /// ```
+    pub fn clear_with(&self, mut f: impl FnMut(K)) {
+        self.inner.clear_with(|k, _| f(k))
+    }
+
+    /// Removes all keys from the set, and yields them as owned values.
+    /// See [`DashMap::drain`] for how this behaves under concurrent inserts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    ///
+    /// let people = DashSet::new();
+    /// people.insert("Albin");
+    /// assert_eq!(people.drain().collect::<Vec<_>>(), ["Albin"]);
+    /// assert!(people.is_empty());
+    /// ```
+    pub fn drain(&'a self) -> Drain<'a, K> {
+        Drain::new(self.inner.drain())
+    }
+
     /// Returns how many keys the set can store without reallocating.
     pub fn capacity(&self) -> usize {
         self.inner.capacity()
@@ -449,6 +571,60 @@ mod tests {
         assert_eq!(set.get(&0).as_deref(), Some(&0));
     }
 
+    #[test]
+    fn test_get_or_insert() {
+        let set = DashSet::new();
+
+        let first = set.get_or_insert(String::with_capacity(16) + "a");
+        assert_eq!(*first, "a");
+        assert!(set.inner.try_get_mut("a").is_locked());
+        drop(first);
+
+        // The element that was inserted first is the one handed out.
+        assert!(set.get_or_insert(String::from("a")).capacity() >= 16);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_try_insert() {
+        let set = DashSet::new();
+
+        let first = set.try_insert(String::with_capacity(16) + "a").unwrap();
+        assert!(set.inner.try_get_mut("a").is_locked());
+        drop(first);
+
+        let key = String::from("a");
+        let key_ptr = key.as_ptr();
+        let rejected = set.try_insert(key).err().unwrap();
+        // The key handed back is the one that was passed in, not the one in the set.
+        assert_eq!(rejected.as_ptr(), key_ptr);
+        assert!(set.get("a").unwrap().capacity() >= 16);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_drain() {
+        let set: DashSet<i32> = (0..100).collect();
+
+        let mut keys: Vec<_> = set.drain().collect();
+        keys.sort_unstable();
+        assert!(keys.into_iter().eq(0..100));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_insert_if_absent() {
+        let set = DashSet::new();
+
+        assert_eq!(set.insert_if_absent(String::with_capacity(16) + "a"), None);
+        let key = String::from("a");
+        let key_ptr = key.as_ptr();
+        let rejected = set.insert_if_absent(key).unwrap();
+        assert_eq!(rejected.as_ptr(), key_ptr);
+        assert!(set.get("a").unwrap().capacity() >= 16);
+        assert!(set.inner.try_get_mut("a").is_present());
+    }
+
     #[test]
     fn test_default() {
         let set: DashSet<u32> = DashSet::default();
@@ -1,7 +1,9 @@
-use core::{mem, ptr};
+use core::{mem, ptr, ptr::NonNull};
 use std::{marker::PhantomData, mem::ManuallyDrop};
 
-use lock_api::{RawRwLock, RawRwLockDowngrade, RwLockReadGuard, RwLockWriteGuard};
+use lock_api::{
+    RawRwLock, RawRwLockDowngrade, RawRwLockRecursive, RwLockReadGuard, RwLockWriteGuard,
+};
 
 pub const fn ptr_size_bits() -> usize {
     mem::size_of::<usize>() * 8
@@ -35,30 +37,64 @@ impl Drop for AbortOnPanic {
 /// A [`RwLockReadGuard`], without the data
 pub(crate) struct RwLockReadGuardDetached<'a, R: RawRwLock> {
     lock: &'a R,
+    data: NonNull<()>,
+    #[cfg(feature = "guard-tracing")]
+    acquired: std::time::Instant,
     _marker: PhantomData<R::GuardMarker>,
 }
 
+// Safety: `data` is only accessed by whoever owns the guard, so these follow `&R` and the guard marker
+// just like the auto traits would without it.
+unsafe impl<R: RawRwLock + Sync> Send for RwLockReadGuardDetached<'_, R> where R::GuardMarker: Send {}
+unsafe impl<R: RawRwLock + Sync> Sync for RwLockReadGuardDetached<'_, R> where R::GuardMarker: Sync {}
+
 impl<R: RawRwLock> Drop for RwLockReadGuardDetached<'_, R> {
     fn drop(&mut self) {
         // Safety: An RwLockReadGuardDetached always holds a shared lock.
         unsafe {
             self.lock.unlock_shared();
         }
+        #[cfg(feature = "guard-tracing")]
+        crate::guard_tracing::report(self.acquired, "read");
+    }
+}
+
+impl<R: RawRwLockRecursive> Clone for RwLockReadGuardDetached<'_, R> {
+    fn clone(&self) -> Self {
+        // The lock is already held in shared mode by `self`, so a recursive
+        // acquisition cannot be blocked by a waiting writer.
+        self.lock.lock_shared_recursive();
+        RwLockReadGuardDetached {
+            lock: self.lock,
+            data: self.data,
+            #[cfg(feature = "guard-tracing")]
+            acquired: std::time::Instant::now(),
+            _marker: PhantomData,
+        }
     }
 }
 
 /// A [`RwLockWriteGuard`], without the data
 pub(crate) struct RwLockWriteGuardDetached<'a, R: RawRwLock> {
     lock: &'a R,
+    data: NonNull<()>,
+    #[cfg(feature = "guard-tracing")]
+    acquired: std::time::Instant,
     _marker: PhantomData<R::GuardMarker>,
 }
 
+// Safety: See `RwLockReadGuardDetached`.
+unsafe impl<R: RawRwLock + Sync> Send for RwLockWriteGuardDetached<'_, R> where R::GuardMarker: Send {}
+unsafe impl<R: RawRwLock + Sync> Sync for RwLockWriteGuardDetached<'_, R> where R::GuardMarker: Sync {}
+
 impl<R: RawRwLock> Drop for RwLockWriteGuardDetached<'_, R> {
     fn drop(&mut self) {
         // Safety: An RwLockWriteGuardDetached always holds an exclusive lock.
         unsafe {
             self.lock.unlock_exclusive();
         }
+        #[cfg(feature = "guard-tracing")]
+        crate::guard_tracing::report(self.acquired, "write");
     }
 }
 
@@ -78,6 +114,10 @@ impl<'a, R: RawRwLock> RwLockReadGuardDetached<'a, R> {
             // Safety: We are imitating the original RwLockReadGuard. It's the callers
             // responsibility to not drop the guard early.
             lock: unsafe { rwlock.raw() },
+            // Safety: The data of a lock is never null.
+            data: unsafe { NonNull::new_unchecked(rwlock.data_ptr()) }.cast(),
+            #[cfg(feature = "guard-tracing")]
+            acquired: std::time::Instant::now(),
             _marker: PhantomData,
         };
         (guard, data)
@@ -100,6 +140,10 @@ impl<'a, R: RawRwLock> RwLockWriteGuardDetached<'a, R> {
             // Safety: We are imitating the original RwLockWriteGuard. It's the callers
             // responsibility to not drop the guard early.
             lock: unsafe { rwlock.raw() },
+            // Safety: The data of a lock is never null.
+            data: unsafe { NonNull::new_unchecked(rwlock.data_ptr()) }.cast(),
+            #[cfg(feature = "guard-tracing")]
+            acquired: std::time::Instant::now(),
             _marker: PhantomData,
         };
         (guard, data)
@@ -119,7 +163,44 @@ impl<'a, R: RawRwLockDowngrade> RwLockWriteGuardDetached<'a, R> {
         unsafe { this.lock.downgrade() }
         RwLockReadGuardDetached {
             lock: this.lock,
+            data: this.data,
+            #[cfg(feature = "guard-tracing")]
+            acquired: this.acquired,
             _marker: this._marker,
         }
     }
 }
+
+impl<'a> RwLockReadGuardDetached<'a, crate::lock::RawRwLock> {
+    /// Turns the shared lock into an exclusive one, if no other shared locks are held.
+    ///
+    /// # Safety
+    ///
+    /// References to the associated data obtained through this guard must not be used after
+    /// a successful upgrade. Use [`RwLockWriteGuardDetached::data_ptr`] instead.
+    pub(crate) unsafe fn try_upgrade(
+        self,
+    ) -> Result<RwLockWriteGuardDetached<'a, crate::lock::RawRwLock>, Self> {
+        // Safety: An RwLockReadGuardDetached always holds a shared lock.
+        if !unsafe { self.lock.try_upgrade() } {
+            return Err(self);
+        }
+
+        // Do not drop the read guard - the shared lock it held is now the exclusive lock
+        let this = ManuallyDrop::new(self);
+        Ok(RwLockWriteGuardDetached {
+            lock: this.lock,
+            data: this.data,
+            #[cfg(feature = "guard-tracing")]
+            acquired: this.acquired,
+            _marker: this._marker,
+        })
+    }
+}
+
+impl<R: RawRwLock> RwLockWriteGuardDetached<'_, R> {
+    /// Returns a pointer to the data this guard was detached from
+    pub(crate) fn data_ptr<T>(&self) -> *mut T {
+        self.data.cast().as_ptr()
+    }
+}